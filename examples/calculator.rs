@@ -1,6 +1,6 @@
 extern crate honeycomb;
 use honeycomb::{
-    atoms::{eof, rec, seq, space, sym},
+    atoms::{chainl1, eof, rec, seq, space},
     language::number,
     transform::to_number,
     Parser,
@@ -25,27 +25,39 @@ fn token(symbol: &'static str) -> Parser<String> {
     space() >> seq(symbol) << space()
 }
 
-fn operation(symbol: char, map_fn: fn((Math, Math)) -> Math) -> Parser<Math> {
-    (number() - to_number - Math::Number | rec(math))
-        .suffix(space() & sym(symbol) & space())
-        .and(rec(math))
-        - map_fn
+/// An operator outputs the function that combines its operands
+type Operator = fn(Math, Math) -> Math;
+
+fn operator(symbol: &'static str, op: Operator) -> Parser<Operator> {
+    token(symbol) - move |_| op
+}
+
+fn add(a: Math, b: Math) -> Math {
+    Math::Add(Arc::new(a), Arc::new(b))
+}
+
+fn multiply(a: Math, b: Math) -> Math {
+    Math::Multiply(Arc::new(a), Arc::new(b))
+}
+
+fn divide(a: Math, b: Math) -> Math {
+    Math::Divide(Arc::new(a), Arc::new(b))
 }
 
-fn add() -> Parser<Math> {
-    operation('+', |m| Math::Add(Arc::new(m.0), Arc::new(m.1)))
+fn subtract(a: Math, b: Math) -> Math {
+    Math::Subtract(Arc::new(a), Arc::new(b))
 }
 
-fn multiply() -> Parser<Math> {
-    operation('*', |m| Math::Multiply(Arc::new(m.0), Arc::new(m.1)))
+fn term() -> Parser<Math> {
+    (number() - to_number - Math::Number) | (token("(") >> rec(sum) << token(")"))
 }
 
-fn divide() -> Parser<Math> {
-    operation('/', |m| Math::Divide(Arc::new(m.0), Arc::new(m.1)))
+fn product() -> Parser<Math> {
+    chainl1(term(), operator("*", multiply) | operator("/", divide))
 }
 
-fn subtract() -> Parser<Math> {
-    operation('-', |m| Math::Subtract(Arc::new(m.0), Arc::new(m.1)))
+fn sum() -> Parser<Math> {
+    chainl1(product(), operator("+", add) | operator("-", subtract))
 }
 
 fn exit() -> Parser<Math> {
@@ -60,9 +72,7 @@ fn math() -> Parser<Math> {
     exit()
         | eof() - (|_| Math::EOF)
         | clear()
-        | token("(") >> rec(math) << token(")")
-        | (number().is()
-            >> (multiply() | divide() | add() | subtract() | (number() - to_number - Math::Number)))
+        | sum()
 }
 
 fn eval(math: Math) -> f64 {
@@ -1,6 +1,6 @@
 extern crate honeycomb;
 use honeycomb::{
-    atoms::{rec, seq_no_ws},
+    atoms::{rec, seq_no_ws, Trailing},
     language,
     transform::{to_btree, to_number},
     Parser,
@@ -36,11 +36,11 @@ fn null() -> Parser<JsonValue> {
 }
 
 fn array() -> Parser<JsonValue> {
-    language::array("[", json(), "]") - JsonValue::Array
+    language::array("[", json(), "]", Trailing::Forbid) - JsonValue::Array
 }
 
 fn object() -> Parser<JsonValue> {
-    language::array("{", string() << seq_no_ws(":") & rec(json), "}") - to_btree - JsonValue::Object
+    language::array("{", string() << seq_no_ws(":") & rec(json), "}", Trailing::Forbid) - to_btree - JsonValue::Object
 }
 
 fn json() -> Parser<JsonValue> {
@@ -1,73 +1,63 @@
-use crate::{Error, Parser};
-use core::cmp::min;
+use crate::{unexpected, Context, Error, Input, MemoKey, Output, Parser};
+
+use core::any::Any;
+use core::fmt::Debug;
 
 use alloc::string::{String, ToString};
+use alloc::sync::Arc;
 /// We need alloc!
 use alloc::vec::Vec;
 
-/// Consumes a character if a function is true
-pub fn if_take(if_fn: fn(char) -> bool) -> Parser<char> {
+/// Consumes a character matching a predicate. Characters are
+/// consumed whole, so multi-byte UTF-8 characters are handled correctly.
+fn satisfy(expectation: String, predicate: impl Fn(char) -> bool + 'static) -> Parser<char> {
+    let expect = expectation.clone();
     Parser::new(
-        move |s: &str| {
-            let result_ch;
-            if let Some(ch) = s.chars().nth(0) {
-                if if_fn(ch) {
-                    return Ok((ch, s[1..].to_string()));
-                }
-                result_ch = ch;
-            } else {
-                result_ch = '\0';
+        move |ctx: &mut Context, offset: usize| match ctx.input()[offset..].chars().next() {
+            Some(ch) if predicate(ch) => Ok((ch, offset + ch.len_utf8())),
+            Some(_) => unexpected(ctx.input(), &expectation, offset),
+            None => {
+                ctx.reached_end();
+                unexpected(ctx.input(), &expectation, offset)
             }
-
-            // Control flow prevents reaching this line unless
-            // 1) EOF in string
-            // 2) if_fn returned false
-            // In these cases, we throw an Error.
-            Error::new(
-                result_ch.to_string(),
-                "result of if_take input".to_string(),
-                s.to_string(),
-            )
         },
-        "result of if_take input".to_string(),
+        expect,
     )
 }
 
+/// Consumes a character if a function is true
+pub fn if_take(if_fn: fn(char) -> bool) -> Parser<char> {
+    satisfy("result of if_take input".to_string(), if_fn)
+}
+
 /// Consumes a matching character
 pub fn sym(symbol: char) -> Parser<char> {
-    Parser::new(
-        move |s: &str| {
-            // If symbol is == s[0], return symbol
-            // Otherwise, return Error
-            if Some(symbol) == s.chars().nth(0) {
-                Ok((symbol, s[1..].to_string()))
-            } else {
-                let actual = match s.chars().nth(0) {
-                    Some(ch) => ch,
-                    None => '\0',
-                };
-                Error::new(actual, symbol, s)
-            }
-        },
-        symbol.to_string(),
-    )
+    satisfy(symbol.to_string(), move |ch| ch == symbol)
 }
 
 /// Consumes a matching sequence of characters
 pub fn seq(sequence: &'static str) -> Parser<String> {
     Parser::new(
-        move |s: &str| {
+        move |ctx: &mut Context, offset: usize| {
+            let s = ctx.input();
             // If every character of sequence is accounted for,
             // consume sequence!
             // Otherwise, return Error
-            let mut n = 0;
-            for ch in sequence.chars() {
-                if s.chars().nth(n) != Some(ch) {
-                    return Error::new(&s[..min(sequence.len(), s.len())], sequence, s);
+            let rest = &s[offset..];
+            if rest.starts_with(sequence) {
+                Ok((sequence.to_string(), offset + sequence.len()))
+            } else {
+                // The rest of the input could be cut off
+                if sequence.starts_with(rest) {
+                    ctx.reached_end();
+                }
+                if rest.is_empty() {
+                    return Error::end(sequence, offset);
                 }
-                n += 1;
+                // Report as many characters as the sequence has
+                let actual = rest.chars().take(sequence.chars().count()).collect::<String>();
+                Error::new(actual, sequence, offset)
             }
-            Ok((s[..n].to_string(), s[n..].to_string()))
         },
         sequence.to_string(),
     )
@@ -79,13 +69,23 @@ pub fn seq_no_ws(sequence: &'static str) -> Parser<String> {
 }
 
 /// Succeeds whether or not the parser consumes input
-pub fn opt<T: 'static + Clone>(parser: Parser<T>) -> Parser<Option<T>> {
+pub fn opt<T, I>(parser: Parser<T, I>) -> Parser<Option<T>, I>
+where
+    T: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
     let expectation = format!("Optionally {}", parser.expectation.clone());
     Parser::new(
-        move |s: &str| match parser.parse_internal(s) {
+        move |ctx: &mut Context<I>, offset: usize| match ctx.alternative(&parser, offset) {
             // Return okay either way!
+            // The error is kept in case parsing fails further on.
             Ok((consumed, remaining)) => Ok((Some(consumed), remaining)),
-            Err(_) => Ok((None, s.to_string())),
+            // Unless we committed to parsing it
+            Err(e) if e.is_fatal() => Err(e),
+            Err(e) => {
+                ctx.record(e);
+                Ok((None, offset))
+            }
         },
         expectation,
     )
@@ -93,86 +93,387 @@ pub fn opt<T: 'static + Clone>(parser: Parser<T>) -> Parser<Option<T>> {
 
 /// Consumes any character
 pub fn any() -> Parser<char> {
+    satisfy("any character".to_string(), |_| true)
+}
+
+/// Consumes the next n bytes of text, which must end on a character
+/// boundary. Lengths in length-prefixed formats like netstrings count
+/// bytes, so this is useful with `Parser::then`, like
+/// `length.then(take)` where `length` is a `Parser<usize>`.
+pub fn take(n: usize) -> Parser<String> {
+    let expectation = format!("{} bytes", n);
+    let expect = expectation.clone();
     Parser::new(
-        move |s: &str| {
-            if let Some(c) = s.chars().nth(0) {
-                Ok((c, s[1..].to_string()))
-            } else {
-                Error::new('\0', "any character", s)
+        move |ctx: &mut Context, offset: usize| {
+            let s = ctx.input();
+            let end = offset.saturating_add(n);
+            match s.get(offset..end) {
+                Some(taken) => Ok((taken.to_string(), end)),
+                None => {
+                    // The rest of the input could be cut off
+                    if end > s.len() {
+                        ctx.reached_end();
+                    }
+                    unexpected(s, &expectation, offset)
+                }
             }
         },
-        "any character",
+        expect,
     )
 }
 
-/// Consumes any of a list of bytes
+/// Consumes any of a list of bytes.
+/// Each byte stands for the character with the same code point,
+/// so a non-ASCII character only matches the byte for its code point.
 pub fn one_of(options: &'static [u8]) -> Parser<char> {
+    let chars = options.iter().map(|n| *n as char).collect::<Vec<char>>();
+    satisfy(format!("one of {:?}", chars), move |ch| {
+        options.iter().any(|n| *n as char == ch)
+    })
+}
+
+/// Consumes anything not in a list of bytes.
+/// Each byte stands for the character with the same code point.
+pub fn none_of(options: &'static [u8]) -> Parser<char> {
+    let chars = options.iter().map(|n| *n as char).collect::<Vec<char>>();
+    satisfy(format!("none of {:?}", chars), move |ch| {
+        !options.iter().any(|n| *n as char == ch)
+    })
+}
+
+/// Consumes any of a list of characters
+pub fn one_of_chars(options: &'static [char]) -> Parser<char> {
+    satisfy(format!("one of {:?}", options), move |ch| options.contains(&ch))
+}
+
+/// Consumes anything not in a list of characters
+pub fn none_of_chars(options: &'static [char]) -> Parser<char> {
+    satisfy(format!("none of {:?}", options), move |ch| !options.contains(&ch))
+}
+
+/// Consumes a matching byte
+pub fn byte(b: u8) -> Parser<u8, [u8]> {
+    tok(b)
+}
+
+/// Consumes a matching sequence of bytes
+pub fn bytes(sequence: &'static [u8]) -> Parser<Vec<u8>, [u8]> {
     Parser::new(
-        move |s: &str| match s.chars().nth(0) {
-            Some(ch) => {
-                if options.contains(&(ch as u8)) {
-                    Ok((ch, s[1..].to_string()))
-                } else {
-                    Error::new(ch, format!("One of {:?}", options), s)
+        move |ctx: &mut Context<[u8]>, offset: usize| {
+            let rest = &ctx.input()[offset..];
+            if rest.starts_with(sequence) {
+                Ok((sequence.to_vec(), offset + sequence.len()))
+            } else {
+                // The rest of the input could be cut off
+                if sequence.starts_with(rest) {
+                    ctx.reached_end();
+                }
+                if rest.is_empty() {
+                    return Error::end(format!("{:?}", sequence), offset);
                 }
+                // Report as many bytes as the sequence has
+                let found = &rest[..sequence.len().min(rest.len())];
+                Error::new(format!("{:?}", found), format!("{:?}", sequence), offset)
             }
-            None => Error::new('\0', format!("One of {:?}", options), s),
         },
-        format!("one of {:?}", options.iter().map(|n| n.clone() as char).collect::<Vec<char>>()),
+        format!("{:?}", sequence),
     )
 }
 
-/// Consumes anything not in a list of bytes
-pub fn none_of(options: &'static [u8]) -> Parser<char> {
+/// Consumes the next n bytes. This is `take` for byte slices,
+/// like `length.then(take_bytes)` where `length` is a
+/// `Parser<usize, [u8]>`.
+pub fn take_bytes(n: usize) -> Parser<Vec<u8>, [u8]> {
+    let expectation = format!("{} bytes", n);
+    let expect = expectation.clone();
     Parser::new(
-        move |s: &str| match s.chars().nth(0) {
-            Some(ch) => {
-                if options.contains(&(ch as u8)) {
-                    Error::new(ch, format!("None of {:?}", options), s)
-                } else {
-                    Ok((ch, s[1..].to_string()))
+        move |ctx: &mut Context<[u8]>, offset: usize| {
+            let s = ctx.input();
+            let end = offset.saturating_add(n);
+            match s.get(offset..end) {
+                Some(taken) => Ok((taken.to_vec(), end)),
+                None => {
+                    // The rest of the input could be cut off
+                    ctx.reached_end();
+                    unexpected(s, &expectation, offset)
                 }
             }
-            None => Error::new('\0', format!("None of {:?}", options), s),
         },
-        format!("none of {:?}", options.iter().map(|n| n.clone() as char).collect::<Vec<char>>()),
+        expect,
+    )
+}
+
+/// Consumes a matching token. Errors show the tokens with Debug.
+pub fn tok<Tok>(token: Tok) -> Parser<Tok, [Tok]>
+where
+    Tok: 'static + Clone + PartialEq + Debug,
+{
+    let expectation = format!("{:?}", token);
+    token_where(move |t| *t == token, |t| Some(format!("{:?}", t)), expectation)
+}
+
+/// Consumes a token if a function is true.
+/// Errors describe the token by its position.
+pub fn tok_if<Tok>(if_fn: impl Fn(&Tok) -> bool + 'static) -> Parser<Tok, [Tok]>
+where
+    Tok: 'static + Clone + PartialEq,
+{
+    token_where(if_fn, |_| None, "result of tok_if input")
+}
+
+/// Consumes a token if a function is true. Any other token is
+/// described by `show`, or by its position if that returns None.
+fn token_where<Tok>(
+    if_fn: impl Fn(&Tok) -> bool + 'static,
+    show: fn(&Tok) -> Option<String>,
+    expectation: impl ToString,
+) -> Parser<Tok, [Tok]>
+where
+    Tok: 'static + Clone + PartialEq,
+{
+    let expectation = expectation.to_string();
+    let expected = expectation.clone();
+    Parser::new(
+        move |ctx: &mut Context<[Tok]>, offset: usize| match ctx.input().get(offset) {
+            Some(t) if if_fn(t) => Ok((t.clone(), offset + 1)),
+            Some(t) => {
+                let actual = show(t).unwrap_or_else(|| ctx.input().describe(offset..offset + 1));
+                Error::new(actual, &expected, offset)
+            }
+            None => {
+                ctx.reached_end();
+                unexpected(ctx.input(), &expected, offset)
+            }
+        },
+        expectation,
+    )
+}
+
+/// Consumes nothing, and commits to the alternative it is in.
+/// If anything after the cut fails, `or`, `opt` and `repeat`
+/// won't backtrack out of the alternative and try something else,
+/// so the error is reported where it happened. For example, once
+/// `seq("{") & cut()` succeeds, the input must be an object.
+/// A cut only commits to the innermost alternative it is in.
+pub fn cut<I>() -> Parser<(), I>
+where
+    I: Input + ?Sized + 'static,
+{
+    Parser::new(
+        move |ctx: &mut Context<I>, offset: usize| {
+            ctx.cut();
+            Ok(((), offset))
+        },
+        "cut",
     )
 }
 
 /// Consumes nothing, but fails if this parser succeeds
-pub fn not<T>(parser: Parser<T>) -> Parser<()>
+pub fn not<T, I>(parser: Parser<T, I>) -> Parser<(), I>
 where
     T: 'static + Clone,
+    I: Input + ?Sized + 'static,
 {
     !parser
 }
 
 /// Consumes nothing, but succeeds if this parser succeeds
-pub fn is<T>(parser: Parser<T>) -> Parser<()>
+pub fn is<T, I>(parser: Parser<T, I>) -> Parser<(), I>
 where
     T: 'static + Clone,
+    I: Input + ?Sized + 'static,
 {
     parser.is()
 }
 
+/// Consumes nothing, and returns the offset of the remaining input
+pub fn position<I>() -> Parser<usize, I>
+where
+    I: Input + ?Sized + 'static,
+{
+    Parser::new(
+        move |_: &mut Context<I>, offset: usize| Ok((offset, offset)),
+        "the current position",
+    )
+}
+
+/// Consumes nothing, and returns the state given to `Parser::parse_with_state`
+pub fn get_state<S, I>() -> Parser<S, I>
+where
+    S: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    Parser::new(
+        move |ctx: &mut Context<I>, offset: usize| Ok((ctx.state::<S>().clone(), offset)),
+        "the current state",
+    )
+}
+
+/// Consumes nothing, and replaces the state given to `Parser::parse_with_state`.
+/// If parsing backtracks from this parser, the old state is restored.
+pub fn set_state<S, I>(state: S) -> Parser<(), I>
+where
+    S: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    Parser::new(
+        move |ctx: &mut Context<I>, offset: usize| {
+            *ctx.state() = state.clone();
+            Ok(((), offset))
+        },
+        "a new state",
+    )
+}
+
 /// Consumes whitespace
+/// Whitespace is always optional, so this never records an error:
+/// "expected whitespace" would never explain why parsing failed.
 pub fn space() -> Parser<String> {
-    one_of(b" \t\r\n").repeat(0..).map(|v| v.iter().collect())
-        % "whitespace"
+    Parser::new(
+        move |ctx: &mut Context, offset: usize| {
+            let s = ctx.input();
+            let rest = &s[offset..];
+            let len = rest.len() - rest.trim_start_matches([' ', '\t', '\r', '\n']).len();
+            // There could be more whitespace after the end
+            if len == rest.len() {
+                ctx.reached_end();
+            }
+            Ok((rest[..len].to_string(), offset + len))
+        },
+        "whitespace",
+    )
 }
 
 /// Consumes EOF
 pub fn eof() -> Parser<()> {
     space().prefixes(Parser::new(
-        move |s: &str| match s.chars().nth(0) {
-            Some('\0') => Ok(((), s.to_string())),
-            Some(ch) => Error::new(ch, "EOF", s),
-            None => Ok(((), s.to_string())),
+        move |ctx: &mut Context, offset: usize| match offset == ctx.input().len() {
+            true => {
+                ctx.reached_end();
+                Ok(((), offset))
+            }
+            false => unexpected(ctx.input(), "EOF", offset),
         },
         "EOF",
     )) % "EOF"
 }
 
+/// Consumes input until the parser would succeed, or until the end of
+/// the input. The input the parser would consume is not consumed.
+/// The output describes the input that was skipped.
+pub fn skip_until<T, I>(parser: Parser<T, I>) -> Parser<String, I>
+where
+    T: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    let expectation = format!("anything until {}", parser.expectation);
+    let stop = parser.is();
+    Parser::new(
+        move |ctx: &mut Context<I>, offset: usize| {
+            let mut end = offset;
+            while let Some(next) = ctx.input().next(end) {
+                if stop.parse_internal(ctx, end).is_ok() {
+                    break;
+                }
+                end = next;
+            }
+            // There could be more to skip after the end
+            if end == ctx.input().len() {
+                ctx.reached_end();
+            }
+            Ok((ctx.input().describe(offset..end), end))
+        },
+        expectation,
+    )
+}
+
+/// If the parser fails, the error is logged and the input is skipped
+/// from where it failed until the sync parser would succeed, like at
+/// the next `;` or `,`. The parser then outputs a placeholder, the
+/// default value of T, so that parsing can carry on and find more errors.
+/// Use `Parser::parse_recovering` to get every error that was logged.
+/// If the parser fails at the end of the input, there is nothing
+/// to skip, so the error isn't recovered from.
+pub fn recover_until<T, S, I>(parser: Parser<T, I>, sync: Parser<S, I>) -> Parser<T, I>
+where
+    T: 'static + Clone + Default,
+    S: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    let expectation = parser.expectation.clone();
+    let skip = skip_until(sync);
+    Parser::new(
+        move |ctx: &mut Context<I>, offset: usize| match ctx.reported(&parser, offset) {
+            Ok(t) => Ok(t),
+            Err(e) if offset == ctx.input().len() => Err(e),
+            Err(e) => {
+                let (_, end) = skip.parse_internal(ctx, e.offset())?;
+                ctx.recover(e);
+                Ok((T::default(), end))
+            }
+        },
+        expectation,
+    )
+}
+
+/// If the parser fails, the error is logged and the input is skipped
+/// past a delimited block, like `{ ... }`, if the parser started at one.
+/// Nested blocks are skipped over, so the block ends at the matching
+/// close delimiter. The parser then outputs a placeholder, the default
+/// value of T, so that parsing can carry on and find more errors.
+/// Use `Parser::parse_recovering` to get every error that was logged.
+/// If the parser didn't start at a block, or the block is never closed,
+/// the error isn't recovered from.
+pub fn delimited_recovery<T>(
+    parser: Parser<T>,
+    open: &'static str,
+    close: &'static str,
+) -> Parser<T>
+where
+    T: 'static + Clone + Default,
+{
+    let expectation = parser.expectation.clone();
+    Parser::new(
+        move |ctx: &mut Context, offset: usize| match ctx.reported(&parser, offset) {
+            Ok(t) => Ok(t),
+            Err(e) => match balanced(ctx.input(), offset, open, close) {
+                Some(end) => {
+                    ctx.recover(e);
+                    Ok((T::default(), end))
+                }
+                None => Err(e),
+            },
+        },
+        expectation,
+    )
+}
+
+/// Find the end of a delimited block starting at an offset, after any
+/// whitespace, including the close delimiter.
+fn balanced(s: &str, offset: usize, open: &str, close: &str) -> Option<usize> {
+    let mut end = s.len() - s[offset..].trim_start().len();
+    if !s[end..].starts_with(open) {
+        return None;
+    }
+
+    let mut depth = 0;
+    while let Some(ch) = s[end..].chars().next() {
+        if s[end..].starts_with(open) {
+            depth += 1;
+            end += open.len();
+        } else if s[end..].starts_with(close) {
+            depth -= 1;
+            end += close.len();
+            if depth == 0 {
+                return Some(end);
+            }
+        } else {
+            end += ch.len_utf8();
+        }
+    }
+    None
+}
+
 /// Consumes a list of items separated by a seperating parser
 /// This will match the following.
 /// A, B, ...
@@ -181,16 +482,18 @@ pub fn eof() -> Parser<()> {
 /// A,
 /// A
 /// The separating parser will not consume input.
-pub fn list<A, B>(parser: Parser<A>, sep: Parser<B>) -> Parser<Vec<A>>
+/// See `sep_by` for lists that need a stricter trailing separator.
+pub fn list<A, B, I>(parser: Parser<A, I>, sep: Parser<B, I>) -> Parser<Vec<A>, I>
 where
     A: 'static + Clone,
     B: 'static + Clone,
+    I: Input + ?Sized + 'static,
 {
     parser
         .clone()
         .suffix(sep)
         .repeat(0..)
-        .and(parser.repeat(0..1))
+        .and(parser.at_most(1))
         .map(|v: (Vec<A>, Vec<A>)| {
             let mut vec = (v.0).clone();
             if (v.1).is_empty() {
@@ -201,13 +504,459 @@ where
         })
 }
 
+/// What a separated list does with a separator after its last item
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Trailing {
+    /// `A, B` but not `A, B,`
+    Forbid,
+    /// `A, B` or `A, B,`
+    Allow,
+    /// `A, B,` but not `A, B`
+    Require,
+}
+
+/// Consumes items separated by a separating parser, with at least
+/// `min` items, and a trailing separator handled by the policy
+fn separated<A, B, I>(
+    item: Parser<A, I>,
+    sep: Parser<B, I>,
+    trailing: Trailing,
+    min: usize,
+) -> Parser<Vec<A>, I>
+where
+    A: 'static + Clone,
+    B: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    let expectation = format!(
+        "{} or more {}(s) separated by {}",
+        min, item.expectation, sep.expectation
+    );
+    Parser::new(
+        move |ctx: &mut Context<I>, offset: usize| {
+            let mut items = vec![];
+            let mut end = match ctx.alternative(&item, offset) {
+                Ok((t, e)) => {
+                    items.push(t);
+                    e
+                }
+                Err(e) if e.is_fatal() || min > 0 => return Err(e),
+                Err(e) => {
+                    ctx.record(e);
+                    return Ok((items, offset));
+                }
+            };
+
+            loop {
+                let after_sep = match ctx.alternative(&sep, end) {
+                    Ok((_, e)) => e,
+                    // The last item needs a separator after it
+                    Err(e) if e.is_fatal() || trailing == Trailing::Require => return Err(e),
+                    Err(e) => {
+                        ctx.record(e);
+                        return Ok((items, end));
+                    }
+                };
+                match ctx.alternative(&item, after_sep) {
+                    // A separator and an item that consume nothing
+                    // would repeat forever, so stop here
+                    Ok((_, e)) if e == end => return Ok((items, end)),
+                    Ok((t, e)) => {
+                        items.push(t);
+                        end = e;
+                    }
+                    // A separator must have an item after it
+                    Err(e) if e.is_fatal() || trailing == Trailing::Forbid => return Err(e),
+                    Err(e) => {
+                        ctx.record(e);
+                        return Ok((items, after_sep));
+                    }
+                }
+            }
+        },
+        expectation,
+    )
+}
+
+/// Consumes zero or more items separated by a separating parser,
+/// with a trailing separator forbidden, allowed, or required.
+pub fn sep_by<A, B, I>(
+    item: Parser<A, I>,
+    sep: Parser<B, I>,
+    trailing: Trailing,
+) -> Parser<Vec<A>, I>
+where
+    A: 'static + Clone,
+    B: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    separated(item, sep, trailing, 0)
+}
+
+/// Consumes one or more items separated by a separating parser,
+/// with a trailing separator forbidden, allowed, or required.
+pub fn sep_by1<A, B, I>(
+    item: Parser<A, I>,
+    sep: Parser<B, I>,
+    trailing: Trailing,
+) -> Parser<Vec<A>, I>
+where
+    A: 'static + Clone,
+    B: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    separated(item, sep, trailing, 1)
+}
+
+/// Consumes zero or more items separated by a separating parser,
+/// which may also come after the last item.
+/// This is `sep_by` with `Trailing::Allow`.
+pub fn sep_end_by<A, B, I>(item: Parser<A, I>, sep: Parser<B, I>) -> Parser<Vec<A>, I>
+where
+    A: 'static + Clone,
+    B: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    separated(item, sep, Trailing::Allow, 0)
+}
+
+/// Consumes one or more items separated by a separating parser,
+/// which may also come after the last item.
+/// This is `sep_by1` with `Trailing::Allow`.
+pub fn sep_end_by1<A, B, I>(item: Parser<A, I>, sep: Parser<B, I>) -> Parser<Vec<A>, I>
+where
+    A: 'static + Clone,
+    B: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    separated(item, sep, Trailing::Allow, 1)
+}
+
+/// Consumes zero or more items that are each followed by a separating
+/// parser, like statements ending in semicolons.
+/// This is `sep_by` with `Trailing::Require`.
+pub fn end_by<A, B, I>(item: Parser<A, I>, sep: Parser<B, I>) -> Parser<Vec<A>, I>
+where
+    A: 'static + Clone,
+    B: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    separated(item, sep, Trailing::Require, 0)
+}
+
+/// Consumes one or more terms separated by operators, and combines
+/// them from the left with the function each operator outputs,
+/// so `1 - 2 - 3` is `(1 - 2) - 3`. The operators can output
+/// function pointers like `fn(T, T) -> T`, or closures.
+pub fn chainl1<T, F, I>(term: Parser<T, I>, op: Parser<F, I>) -> Parser<T, I>
+where
+    T: 'static + Clone,
+    F: Fn(T, T) -> T + 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    let expectation = format!("{} chained with {}", term.expectation, op.expectation);
+    let rest = op & term.clone();
+    Parser::new(
+        move |ctx: &mut Context<I>, offset: usize| {
+            let (mut acc, mut end) = term.parse_internal(ctx, offset)?;
+            loop {
+                match ctx.alternative(&rest, end) {
+                    // An operator and term that consume nothing
+                    // would repeat forever, so stop here
+                    Ok((_, e)) if e == end => return Ok((acc, end)),
+                    Ok(((f, t), e)) => {
+                        acc = f(acc, t);
+                        end = e;
+                    }
+                    Err(e) if e.is_fatal() => return Err(e),
+                    Err(e) => {
+                        ctx.record(e);
+                        return Ok((acc, end));
+                    }
+                }
+            }
+        },
+        expectation,
+    )
+}
+
+/// Parses the operators and terms after the first term of a chain,
+/// and combines them from the right
+fn chain_right<T, F, I>(
+    ctx: &mut Context<I>,
+    first: (T, usize),
+    rest: &Parser<(F, T), I>,
+) -> Output<T>
+where
+    T: 'static + Clone,
+    F: Fn(T, T) -> T + 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    let (lhs, end) = first;
+    match ctx.alternative(rest, end) {
+        Ok((_, e)) if e == end => Ok((lhs, end)),
+        Ok(((f, rhs), e)) => {
+            let (rhs, e) = chain_right(ctx, (rhs, e), rest)?;
+            Ok((f(lhs, rhs), e))
+        }
+        Err(e) if e.is_fatal() => Err(e),
+        Err(e) => {
+            ctx.record(e);
+            Ok((lhs, end))
+        }
+    }
+}
+
+/// Consumes one or more terms separated by operators, and combines
+/// them from the right with the function each operator outputs,
+/// so `2 ^ 3 ^ 2` is `2 ^ (3 ^ 2)`.
+pub fn chainr1<T, F, I>(term: Parser<T, I>, op: Parser<F, I>) -> Parser<T, I>
+where
+    T: 'static + Clone,
+    F: Fn(T, T) -> T + 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    let expectation = format!("{} chained with {}", term.expectation, op.expectation);
+    let rest = op & term.clone();
+    Parser::new(
+        move |ctx: &mut Context<I>, offset: usize| {
+            let first = term.parse_internal(ctx, offset)?;
+            chain_right(ctx, first, &rest)
+        },
+        expectation,
+    )
+}
+
+/// Consumes zero or more of a parser, and folds each output into
+/// an accumulator that starts as `init`. This is like `repeat`
+/// followed by a fold, but without collecting the outputs first.
+pub fn fold_many<T, A, F, I>(parser: Parser<T, I>, init: A, f: F) -> Parser<A, I>
+where
+    T: 'static + Clone,
+    A: 'static + Clone,
+    F: Fn(A, T) -> A + 'static,
+    I: Input + ?Sized + 'static,
+{
+    let expectation = format!("any number of {}", parser.expectation);
+    Parser::new(
+        move |ctx: &mut Context<I>, offset: usize| {
+            let mut acc = init.clone();
+            let mut end = offset;
+            loop {
+                match ctx.alternative(&parser, end) {
+                    // Stop if the parser consumed nothing, like `repeat`
+                    Ok((_, e)) if e == end => return Ok((acc, end)),
+                    Ok((t, e)) => {
+                        acc = f(acc, t);
+                        end = e;
+                    }
+                    Err(e) if e.is_fatal() => return Err(e),
+                    Err(e) => {
+                        ctx.record(e);
+                        return Ok((acc, end));
+                    }
+                }
+            }
+        },
+        expectation,
+    )
+}
+
 /// This allows us to make recursive parsers
-pub fn rec<T>(parser: fn() -> Parser<T>) -> Parser<T>
+pub fn rec<T, I>(parser: fn() -> Parser<T, I>) -> Parser<T, I>
 where
     T: 'static + Clone,
+    I: Input + ?Sized + 'static,
 {
     Parser::new(
-        move |s| parser().parse_internal(s),
+        move |ctx, offset| parser().parse_internal(ctx, offset),
         "result from recursive Parser",
     )
 }
+
+/// This allows us to make recursive parsers that are memoized.
+/// The results of the rule are remembered at every offset it is used at,
+/// and shared between every place the rule is used, so a grammar that
+/// tries the same rule at the same offset many times runs in linear time.
+/// The rule is identified by the type of the function that builds it,
+/// so it must be a function like `expr`, or a closure that captures
+/// nothing. A function pointer or a capturing closure won't compile.
+pub fn memo_rec<T, I, F>(parser: F) -> Parser<T, I>
+where
+    T: 'static + Clone,
+    I: Input + ?Sized + 'static,
+    F: Fn() -> Parser<T, I> + 'static,
+{
+    let key = MemoKey::rule(&parser);
+    Parser::new(
+        move |ctx, offset| parser().parse_internal(ctx, offset),
+        "result from recursive Parser",
+    )
+    .memoize(key)
+}
+
+/// This allows us to make left recursive parsers, like
+/// `expr := expr '+' term | term`, which would otherwise recurse forever.
+/// The results are memoized like `memo_rec`, and grown from the
+/// alternatives that don't recurse, so the result associates to the left.
+/// Rules that are indirectly left recursive should all use `left_rec`,
+/// and a left recursive rule must always be used through `left_rec`,
+/// even when it's the outermost parser. The rule is identified by its
+/// function like in `memo_rec`.
+pub fn left_rec<T, I, F>(parser: F) -> Parser<T, I>
+where
+    T: 'static + Clone,
+    I: Input + ?Sized + 'static,
+    F: Fn() -> Parser<T, I> + 'static,
+{
+    let key = MemoKey::rule(&parser);
+    Parser::new(
+        move |ctx, offset| ctx.grow(key, &parser, offset),
+        "result from recursive Parser",
+    )
+}
+
+/// A member of a permutation, with its output type erased
+type Member<I> = Parser<Arc<dyn Any>, I>;
+
+/// A tuple of parsers that `permutation` and `permutation_opt`
+/// can consume in any order. This is implemented for tuples of
+/// two to eight parsers.
+pub trait Permutation<I: ?Sized> {
+    /// The outputs of every parser in the tuple
+    type Output;
+    /// The outputs of the parsers in the tuple that were found
+    type Optional;
+
+    /// The parsers in the tuple, in order
+    fn members(self) -> Vec<Member<I>>;
+    /// The outputs of the tuple, when every member was found
+    fn output(found: &[Option<Arc<dyn Any>>]) -> Self::Output;
+    /// The outputs of the tuple, with the members that weren't found
+    fn optional(found: &[Option<Arc<dyn Any>>]) -> Self::Optional;
+}
+
+macro_rules! permutation_tuple {
+    ($($T:ident $n:tt),+) => {
+        impl<$($T,)+ I> Permutation<I> for ($(Parser<$T, I>,)+)
+        where
+            $($T: 'static + Clone,)+
+            I: Input + ?Sized + 'static,
+        {
+            type Output = ($($T,)+);
+            type Optional = ($(Option<$T>,)+);
+
+            fn members(self) -> Vec<Member<I>> {
+                vec![$(self.$n.map(|t| Arc::new(t) as Arc<dyn Any>),)+]
+            }
+
+            fn output(found: &[Option<Arc<dyn Any>>]) -> Self::Output {
+                let found = Self::optional(found);
+                ($(found.$n.expect("every member of the permutation was found"),)+)
+            }
+
+            fn optional(found: &[Option<Arc<dyn Any>>]) -> Self::Optional {
+                ($(found[$n].as_ref().and_then(|t| t.downcast_ref::<$T>()).cloned(),)+)
+            }
+        }
+    };
+}
+
+permutation_tuple!(A 0, B 1);
+permutation_tuple!(A 0, B 1, C 2);
+permutation_tuple!(A 0, B 1, C 2, D 3);
+permutation_tuple!(A 0, B 1, C 2, D 3, E 4);
+permutation_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+permutation_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+permutation_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+/// Consumes each member at most once in any order, and fails if a
+/// member comes twice, or if `required` and a member is missing.
+fn permute<I>(members: Vec<Member<I>>, required: bool) -> Parser<Vec<Option<Arc<dyn Any>>>, I>
+where
+    I: Input + ?Sized + 'static,
+{
+    let names = members
+        .iter()
+        .map(|member| member.expectation.clone())
+        .collect::<Vec<_>>()
+        .join(", ");
+    Parser::new(
+        move |ctx: &mut Context<I>, offset: usize| {
+            let mut found: Vec<Option<Arc<dyn Any>>> = vec![None; members.len()];
+            let mut end = offset;
+            'next: loop {
+                for (i, member) in members.iter().enumerate() {
+                    if found[i].is_some() {
+                        continue;
+                    }
+                    match ctx.alternative(member, end) {
+                        Ok((t, e)) => {
+                            found[i] = Some(t);
+                            end = e;
+                            continue 'next;
+                        }
+                        Err(e) if e.is_fatal() => return Err(e),
+                        Err(e) => ctx.record(e),
+                    }
+                }
+                break;
+            }
+
+            // A member that was already found can't come again
+            for (i, member) in members.iter().enumerate() {
+                if found[i].is_none() {
+                    continue;
+                }
+                if let Ok((_, e)) = ctx.lookahead(member, end) {
+                    if e > end {
+                        let expected = format!("at most one {}", member.expectation);
+                        return unexpected(ctx.input(), expected, end);
+                    }
+                }
+            }
+
+            if required {
+                let missing = members
+                    .iter()
+                    .zip(&found)
+                    .filter(|(_, found)| found.is_none())
+                    .map(|(member, _)| {
+                        unexpected::<(), _>(ctx.input(), &member.expectation, end).unwrap_err()
+                    })
+                    .reduce(Error::merge);
+                if let Some(error) = missing {
+                    return Err(error);
+                }
+            }
+            Ok((found, end))
+        },
+        format!("{} in any order", names),
+    )
+}
+
+/// Consumes every parser in a tuple exactly once, in any order,
+/// and outputs a tuple of their outputs in the order of the parsers.
+/// The error names the members that are missing, or a member
+/// that comes twice.
+/// Here's an example: `permutation((sym('a'), sym('b'), sym('c')))`
+pub fn permutation<P, I>(parsers: P) -> Parser<P::Output, I>
+where
+    P: Permutation<I>,
+    P::Output: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    permute(parsers.members(), true).map(|found| P::output(&found))
+}
+
+/// Consumes every parser in a tuple at most once, in any order,
+/// and outputs a tuple with the outputs of the parsers that were found.
+/// The error names a member that comes twice.
+pub fn permutation_opt<P, I>(parsers: P) -> Parser<P::Optional, I>
+where
+    P: Permutation<I>,
+    P::Optional: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    permute(parsers.members(), false).map(|found| P::optional(&found))
+}
@@ -1,13 +1,15 @@
 use crate::{
-    atoms::{opt, seq_no_ws, space, sym},
+    atoms::{byte, opt, rec, seq_no_ws, space, sym, take_bytes, tok_if},
     language::{alpha, alphanumeric, identifier, numeral},
     transform::collect,
-    Parser,
+    Context, Parser,
 };
 
 use core::fmt::{Display, Error, Formatter};
 
+use alloc::collections::BTreeMap;
 use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 
 /// Parses an email address and returns the component preceding the '@' symbol
 /// and the domain following the '@' symbol as a tuple.
@@ -48,32 +50,115 @@ impl Display for PhoneNumber {
 
 /// Consumes a phone number
 pub fn phone_number() -> Parser<PhoneNumber> {
-    let country_code = seq_no_ws("+") >> ((numeral() * (..3)) - collect);
-    let area_code = opt(seq_no_ws("-")) >> ((numeral() * (3..3)) - collect);
-    let prefix = opt(seq_no_ws("-")) >> ((numeral() * (3..3)) - collect);
-    let line_number = opt(seq_no_ws("-")) >> ((numeral() * (4..4)) - collect);
+    let country_code = seq_no_ws("+") >> ((numeral() * (..=3)) - collect);
+    let area_code = opt(seq_no_ws("-")) >> (numeral().exactly(3) - collect);
+    let prefix = opt(seq_no_ws("-")) >> (numeral().exactly(3) - collect);
+    let line_number = opt(seq_no_ws("-")) >> (numeral().exactly(4) - collect);
 
-    ((opt(country_code) & (area_code & (prefix & line_number)))
+    (((opt(country_code) & (area_code & (prefix & line_number)))
         - |s: (Option<String>, (String, (String, String)))| PhoneNumber {
             country_code: s.0,
             area_code: (s.1).0,
             prefix: ((s.1).1).0,
             line_number: ((s.1).1).1,
-        }) % "a valid phone number"
-        | ((((space() >> numeral()) * (13..13)) << space())
+        }) % "a valid phone number")
+        | ((((space() >> numeral()).exactly(13) << space())
             - collect
             - |s: String| PhoneNumber {
                 country_code: Some(s[0..3].to_string()),
                 area_code: s[3..6].to_string(),
                 prefix: s[6..9].to_string(),
                 line_number: s[9..13].to_string(),
-            }) % "a valid phone number"
-        | ((((space() >> numeral()) * (10..10)) << space())
+            }) % "a valid phone number")
+        | ((((space() >> numeral()).exactly(10) << space())
             - collect
             - |s: String| PhoneNumber {
                 country_code: None,
                 area_code: s[0..3].to_string(),
                 prefix: s[3..6].to_string(),
                 line_number: s[6..10].to_string(),
-            }) % "a valid phone number"
+            }) % "a valid phone number")
+}
+
+/// Consumes a decimal number without leading zeros, like the lengths
+/// and integers in bencode and netstrings
+fn digits() -> Parser<String, [u8]> {
+    (tok_if(|b: &u8| b.is_ascii_digit()) * (1..))
+        ^ |digits: Vec<u8>| match digits.starts_with(b"0") && digits.len() > 1 {
+            true => Err(()),
+            // The digits are ASCII
+            false => String::from_utf8(digits).map_err(|_| ()),
+        }
+}
+
+/// Consumes a length prefix and the colon after it
+fn length() -> Parser<usize, [u8]> {
+    ((digits() ^ |digits: String| digits.parse::<usize>()) << byte(b':')) % "a length"
+}
+
+/// A value in the bencode format used by torrent files.
+/// Byte strings and dictionary keys are raw bytes,
+/// since they don't have to be text.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Bencode {
+    Int(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Self>),
+    Dict(BTreeMap<Vec<u8>, Self>),
+}
+
+/// Consumes a bencoded byte string, like `5:hello`
+fn byte_string() -> Parser<Vec<u8>, [u8]> {
+    length().then(take_bytes) % "a byte string"
+}
+
+/// Consumes the entries of a dictionary. Keys must be unique and
+/// sorted, so every dictionary has exactly one encoding.
+fn entries() -> Parser<BTreeMap<Vec<u8>, Bencode>, [u8]> {
+    let entry = byte_string() & rec(bencode);
+    Parser::new(
+        move |ctx: &mut Context<[u8]>, mut offset: usize| {
+            let mut map = BTreeMap::new();
+            while ctx.input().get(offset) != Some(&b'e') {
+                let ((key, value), end) = entry.parse_internal(ctx, offset)?;
+                if map.keys().next_back().is_some_and(|last| key <= *last) {
+                    // No other parse could fix the order of the keys
+                    let actual = String::from_utf8_lossy(&key).into_owned();
+                    return crate::Error::new(actual, "a key sorted after the last one", offset)
+                        .map_err(|e| e.fatal());
+                }
+                map.insert(key, value);
+                offset = end;
+            }
+            Ok((map, offset))
+        },
+        "dictionary entries",
+    )
+}
+
+/// Consumes a bencoded value. Integers look like `i-42e`,
+/// byte strings like `5:hello`, lists like `li1ei2ee`,
+/// and dictionaries like `d3:keyi1ee`. Dictionary keys must be
+/// unique and sorted, so every value has exactly one encoding.
+pub fn bencode() -> Parser<Bencode, [u8]> {
+    let int = (byte(b'i') >> (opt(byte(b'-')) & digits()) << byte(b'e'))
+        ^ |(sign, digits): (Option<u8>, String)| match sign {
+            // Negative zero isn't allowed
+            Some(_) if digits == "0" => Err(()),
+            Some(_) => format!("-{}", digits).parse::<i64>().map_err(|_| ()),
+            None => digits.parse::<i64>().map_err(|_| ()),
+        };
+    let list = byte(b'l') >> (rec(bencode) * (..)) << byte(b'e');
+    let dict = byte(b'd') >> entries() << byte(b'e');
+
+    ((int - Bencode::Int)
+        | (byte_string() - Bencode::Bytes)
+        | (list - Bencode::List)
+        | (dict - Bencode::Dict))
+        % "a bencoded value"
+}
+
+/// Consumes a netstring, like `5:hello,`, and returns its contents
+pub fn netstring() -> Parser<Vec<u8>, [u8]> {
+    (length().then(take_bytes) << byte(b',')) % "a netstring"
 }
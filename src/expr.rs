@@ -0,0 +1,353 @@
+// This module contains a Pratt parser, which builds a parser
+// for expressions out of an atom parser and a table of operators.
+
+use crate::{Context, Error, Input, Output, Parser};
+
+use alloc::sync::Arc;
+/// We need alloc!
+use alloc::vec::Vec;
+
+/// How an infix operator groups with other operators of the same power
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Assoc {
+    /// `a - b - c` is `(a - b) - c`
+    Left,
+    /// `a ^ b ^ c` is `a ^ (b ^ c)`
+    Right,
+    /// `a < b < c` isn't allowed
+    None,
+}
+
+type PrefixFn<T> = Arc<dyn Fn(T) -> T>;
+type InfixFn<T> = Arc<dyn Fn(T, T) -> T>;
+type TernaryFn<T> = Arc<dyn Fn(T, T, T) -> T>;
+
+/// An operator in the table. Operator parsers output a function
+/// that builds the expression from its operands, so operators whose
+/// parsers output different types can live in the same table.
+struct Operator<F, I: ?Sized> {
+    power: u32,
+    assoc: Assoc,
+    op: Parser<F, I>,
+}
+
+impl<F, I: ?Sized> Clone for Operator<F, I> {
+    fn clone(&self) -> Self {
+        Self {
+            power: self.power,
+            assoc: self.assoc,
+            op: self.op.clone(),
+        }
+    }
+}
+
+/// The first half of a ternary operator, and the second half,
+/// which outputs the function that builds the expression
+type Ternary<T, I> = (Operator<(), I>, Parser<TernaryFn<T>, I>);
+
+/// The binding powers of an operator, from its power and associativity.
+/// The left power is how tightly it binds to the expression before it,
+/// and the right power how tightly it binds to the expression after it.
+fn binding_power(power: u32, assoc: Assoc) -> (u64, u64) {
+    let power = 2 * power as u64;
+    match assoc {
+        Assoc::Left | Assoc::None => (power + 1, power + 2),
+        Assoc::Right => (power + 2, power + 1),
+    }
+}
+
+/// A Pratt parser builds a parser for expressions from a parser for
+/// atoms, like numbers or parenthesized expressions, and the operators
+/// that combine them. Operators with a higher power bind tighter.
+///
+/// ```rust
+/// fn math() -> Parser<f64> {
+///     let atom = (number() - to_number) | (seq_no_ws("(") >> rec(math) << seq_no_ws(")"));
+///     Pratt::new(atom)
+///         .infix(1, Assoc::Left, seq_no_ws("+"), |a, _, b| a + b)
+///         .infix(1, Assoc::Left, seq_no_ws("-"), |a, _, b| a - b)
+///         .infix(2, Assoc::Left, seq_no_ws("*"), |a, _, b| a * b)
+///         .infix(3, Assoc::Right, seq_no_ws("^"), |a, _, b| a.powf(b))
+///         .prefix(4, seq_no_ws("-"), |_, a| -a)
+///         .parser()
+/// }
+/// ```
+pub struct Pratt<T, I: ?Sized = str> {
+    atom: Parser<T, I>,
+    prefix: Vec<Operator<PrefixFn<T>, I>>,
+    infix: Vec<Operator<InfixFn<T>, I>>,
+    postfix: Vec<Operator<PrefixFn<T>, I>>,
+    ternary: Vec<Ternary<T, I>>,
+}
+
+impl<T, I: ?Sized> Clone for Pratt<T, I> {
+    fn clone(&self) -> Self {
+        Self {
+            atom: self.atom.clone(),
+            prefix: self.prefix.clone(),
+            infix: self.infix.clone(),
+            postfix: self.postfix.clone(),
+            ternary: self.ternary.clone(),
+        }
+    }
+}
+
+impl<T, I> Pratt<T, I>
+where
+    T: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    /// Create a Pratt parser for expressions made from these atoms
+    pub fn new(atom: Parser<T, I>) -> Self {
+        Self {
+            atom,
+            prefix: vec![],
+            infix: vec![],
+            postfix: vec![],
+            ternary: vec![],
+        }
+    }
+
+    /// Add a prefix operator, like `-a`.
+    /// The function receives the operator's output and the operand.
+    pub fn prefix<O>(
+        mut self,
+        power: u32,
+        op: Parser<O, I>,
+        f: impl Fn(O, T) -> T + 'static,
+    ) -> Self
+    where
+        O: 'static + Clone,
+    {
+        let f = Arc::new(f);
+        let op = op.map(move |o| -> PrefixFn<T> {
+            let f = f.clone();
+            Arc::new(move |a| f(o.clone(), a))
+        });
+        self.prefix.push(Operator {
+            power,
+            assoc: Assoc::Left,
+            op,
+        });
+        self
+    }
+
+    /// Add an infix operator, like `a + b`.
+    /// The function receives the left operand, the operator's output,
+    /// and the right operand.
+    pub fn infix<O>(
+        mut self,
+        power: u32,
+        assoc: Assoc,
+        op: Parser<O, I>,
+        f: impl Fn(T, O, T) -> T + 'static,
+    ) -> Self
+    where
+        O: 'static + Clone,
+    {
+        let f = Arc::new(f);
+        let op = op.map(move |o| -> InfixFn<T> {
+            let f = f.clone();
+            Arc::new(move |a, b| f(a, o.clone(), b))
+        });
+        self.infix.push(Operator { power, assoc, op });
+        self
+    }
+
+    /// Add a postfix operator, like `a!`.
+    /// The function receives the operand and the operator's output.
+    pub fn postfix<O>(
+        mut self,
+        power: u32,
+        op: Parser<O, I>,
+        f: impl Fn(T, O) -> T + 'static,
+    ) -> Self
+    where
+        O: 'static + Clone,
+    {
+        let f = Arc::new(f);
+        let op = op.map(move |o| -> PrefixFn<T> {
+            let f = f.clone();
+            Arc::new(move |a| f(a, o.clone()))
+        });
+        self.postfix.push(Operator {
+            power,
+            assoc: Assoc::Left,
+            op,
+        });
+        self
+    }
+
+    /// Add a ternary operator, like `a ? b : c`, which groups to the right.
+    /// Any expression can go between the two halves of the operator.
+    /// The function receives the three operands.
+    pub fn ternary<A, B>(
+        mut self,
+        power: u32,
+        first: Parser<A, I>,
+        second: Parser<B, I>,
+        f: impl Fn(T, T, T) -> T + 'static,
+    ) -> Self
+    where
+        A: 'static + Clone,
+        B: 'static + Clone,
+    {
+        let f: TernaryFn<T> = Arc::new(f);
+        let op = Operator {
+            power,
+            assoc: Assoc::Right,
+            op: first.map(|_| ()),
+        };
+        self.ternary.push((op, second.map(move |_| f.clone())));
+        self
+    }
+
+    /// Build the parser for expressions
+    pub fn parser(self) -> Parser<T, I> {
+        Parser::new(
+            move |ctx: &mut Context<I>, offset: usize| self.expression(ctx, offset, 0),
+            "an expression",
+        )
+    }
+
+    /// Parse an expression whose operators all bind
+    /// at least as tightly as `min_power`
+    fn expression(&self, ctx: &mut Context<I>, offset: usize, min_power: u64) -> Output<T> {
+        let (mut lhs, mut offset) = self.operand(ctx, offset)?;
+        // The power of the last non-associative operator, which can't
+        // be followed by another operator of the same power
+        let mut non_assoc = None;
+
+        'operators: loop {
+            for postfix in &self.postfix {
+                let (left, _) = binding_power(postfix.power, postfix.assoc);
+                if left < min_power {
+                    continue;
+                }
+                match ctx.alternative(&postfix.op, offset) {
+                    Ok((f, end)) => {
+                        lhs = f(lhs);
+                        offset = end;
+                        continue 'operators;
+                    }
+                    Err(e) if e.is_fatal() => return Err(e),
+                    Err(e) => ctx.record(e),
+                }
+            }
+
+            for infix in &self.infix {
+                let (left, right) = binding_power(infix.power, infix.assoc);
+                if left < min_power {
+                    continue;
+                }
+                let (f, end) = match ctx.alternative(&infix.op, offset) {
+                    Ok(op) => op,
+                    Err(e) if e.is_fatal() => return Err(e),
+                    Err(e) => {
+                        ctx.record(e);
+                        continue;
+                    }
+                };
+                if non_assoc == Some(infix.power) && infix.assoc == Assoc::None {
+                    // Point at the operator itself, not the whitespace before it.
+                    // Only text can start with whitespace, and text is
+                    // described as itself, so this only moves text offsets.
+                    let text = ctx.input().describe(offset..end);
+                    let actual = text.trim();
+                    let start = offset + text.find(actual).unwrap_or(0);
+                    let expected = format!("anything but {}, which is non-associative", actual);
+                    ctx.record(Error::new::<()>(actual, expected, start).unwrap_err());
+                    break 'operators;
+                }
+                // If there is no right operand, the operator
+                // belongs to whatever comes after this expression
+                match ctx.attempt(|ctx| self.expression(ctx, end, right)) {
+                    Ok((rhs, end)) => {
+                        lhs = f(lhs, rhs);
+                        offset = end;
+                        non_assoc = match infix.assoc {
+                            Assoc::None => Some(infix.power),
+                            _ => None,
+                        };
+                        continue 'operators;
+                    }
+                    Err(e) if e.is_fatal() => return Err(e),
+                    Err(e) => {
+                        ctx.record(e);
+                        break 'operators;
+                    }
+                }
+            }
+
+            for (first, second) in &self.ternary {
+                let (left, right) = binding_power(first.power, first.assoc);
+                if left < min_power {
+                    continue;
+                }
+                let end = match ctx.alternative(&first.op, offset) {
+                    Ok((_, end)) => end,
+                    Err(e) if e.is_fatal() => return Err(e),
+                    Err(e) => {
+                        ctx.record(e);
+                        continue;
+                    }
+                };
+                let middle = ctx.attempt(|ctx| {
+                    let (middle, end) = self.expression(ctx, end, 0)?;
+                    let (f, end) = second.parse_internal(ctx, end)?;
+                    let (rhs, end) = self.expression(ctx, end, right)?;
+                    Ok((f(lhs.clone(), middle, rhs), end))
+                });
+                match middle {
+                    Ok((result, end)) => {
+                        lhs = result;
+                        offset = end;
+                        continue 'operators;
+                    }
+                    Err(e) if e.is_fatal() => return Err(e),
+                    Err(e) => {
+                        ctx.record(e);
+                        break 'operators;
+                    }
+                }
+            }
+
+            break;
+        }
+
+        Ok((lhs, offset))
+    }
+
+    /// Parse an atom, or a prefix operator applied to an expression
+    fn operand(&self, ctx: &mut Context<I>, offset: usize) -> Output<T> {
+        for prefix in &self.prefix {
+            let (f, end) = match ctx.alternative(&prefix.op, offset) {
+                Ok(op) => op,
+                Err(e) if e.is_fatal() => return Err(e),
+                Err(e) => {
+                    ctx.record(e);
+                    continue;
+                }
+            };
+            let (_, right) = binding_power(prefix.power, prefix.assoc);
+            match ctx.attempt(|ctx| self.expression(ctx, end, right)) {
+                Ok((operand, end)) => return Ok((f(operand), end)),
+                Err(e) if e.is_fatal() => return Err(e),
+                Err(e) => ctx.record(e),
+            }
+        }
+
+        self.atom.parse_internal(ctx, offset)
+    }
+}
+
+/// A Pratt parser can be used anywhere a Parser can
+impl<T, I> From<Pratt<T, I>> for Parser<T, I>
+where
+    T: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    fn from(pratt: Pratt<T, I>) -> Self {
+        pratt.parser()
+    }
+}
@@ -0,0 +1,6 @@
+// This module contains parsers for whole data formats,
+// built out of the atoms and language parsers.
+
+/// JSON as described by RFC 8259, with a strict parser, a relaxed
+/// JSON5 parser, and a serializer back to text.
+pub mod json;
@@ -0,0 +1,421 @@
+// This module contains a strict JSON parser, following RFC 8259,
+// a relaxed JSON5 parser for config files, and the JsonValue type
+// they produce.
+
+use crate::{
+    atoms::{any, eof, if_take, one_of, opt, sep_by, seq, skip_until, space, sym, Trailing},
+    transform::collect,
+    unexpected, Context, Parser,
+};
+
+use core::cell::{Cell, OnceCell};
+use core::fmt::{Display, Error, Formatter, Write};
+
+/// We need alloc!
+use alloc::string::String;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+/// The deepest arrays and objects can be nested inside each other.
+/// Each level of nesting recurses, so deeper documents are rejected
+/// instead of overflowing the stack.
+pub const MAX_DEPTH: usize = 128;
+
+/// A JSON value. Objects keep their members in order, along with
+/// any duplicate keys, since RFC 8259 leaves what those mean up
+/// to the application.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Num(f64),
+    Str(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// Get the value of a member of an object. If the key is
+    /// duplicated, the last value wins, like most JSON parsers.
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        match self {
+            Self::Object(members) => members.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+/// Write a string with the characters JSON requires to be escaped
+fn write_str(f: &mut Formatter, s: &str) -> Result<(), Error> {
+    f.write_char('"')?;
+    for ch in s.chars() {
+        match ch {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            '\x08' => f.write_str("\\b")?,
+            '\x0C' => f.write_str("\\f")?,
+            ch if ch < ' ' => write!(f, "\\u{:04x}", ch as u32)?,
+            ch => f.write_char(ch)?,
+        }
+    }
+    f.write_char('"')
+}
+
+/// Displaying a JsonValue serializes it as compact JSON text.
+/// Numbers that JSON can't represent, like NaN, are written as null.
+impl Display for JsonValue {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        match self {
+            Self::Null => f.write_str("null"),
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::Num(n) if n.is_finite() => write!(f, "{}", n),
+            Self::Num(_) => f.write_str("null"),
+            Self::Str(s) => write_str(f, s),
+            Self::Array(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_char(']')
+            }
+            Self::Object(members) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_str(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+/// The differences between strict JSON and JSON5
+trait Dialect: 'static {
+    /// Consumes whitespace, and comments if they're allowed
+    fn space() -> Parser<()>;
+    /// Consumes a number, without the whitespace around it
+    fn number() -> Parser<f64>;
+    /// Consumes a string, without the whitespace around it
+    fn string() -> Parser<String>;
+    /// Consumes the key of a member of an object
+    fn key() -> Parser<String>;
+    /// Whether arrays and objects can end with a comma
+    const TRAILING: Trailing;
+}
+
+/// JSON as described by RFC 8259
+struct Strict;
+
+impl Dialect for Strict {
+    fn space() -> Parser<()> {
+        space() - |_| ()
+    }
+
+    fn number() -> Parser<f64> {
+        ((opt(sym('-')) & unsigned())
+            ^ |(sign, n): (Option<char>, String)| format!("{}{}", sign.unwrap_or('+'), n).parse())
+            % "a number"
+    }
+
+    fn string() -> Parser<String> {
+        let escape = (one_of(b"\"\\/bfnrt") - short_escape) - units;
+        // Control characters must be escaped
+        quoted('"', |ch| ch >= ' ', escape)
+    }
+
+    fn key() -> Parser<String> {
+        Self::string()
+    }
+
+    const TRAILING: Trailing = Trailing::Forbid;
+}
+
+/// JSON5, which adds comments, unquoted keys, single quoted strings
+/// with more escapes, hex numbers, numbers like `.5` and `5.`,
+/// `Infinity` and `NaN`, and trailing commas
+struct Relaxed;
+
+impl Dialect for Relaxed {
+    fn space() -> Parser<()> {
+        let white = if_take(relaxed_space) * (..);
+        let line = seq("//") >> skip_until(sym('\n'));
+        let block = seq("/*") >> skip_until(seq("*/")) << seq("*/");
+        let comment = line | block;
+        (white.clone() >> (comment >> white).repeat(..)) - |_| ()
+    }
+
+    fn number() -> Parser<f64> {
+        let hex = (seq("0x") | seq("0X"))
+            >> ((one_of(b"0123456789abcdefABCDEF").repeat(1..) - collect)
+                ^ |digits: String| u64::from_str_radix(&digits, 16).map(|n| n as f64));
+        let magnitude = hex
+            | (seq("Infinity") - |_| f64::INFINITY)
+            | (seq("NaN") - |_| f64::NAN)
+            | (decimal() ^ |n: String| n.parse::<f64>());
+        ((opt(one_of(b"+-")) & magnitude)
+            - |(sign, n): (Option<char>, f64)| if sign == Some('-') { -n } else { n })
+            % "a number"
+    }
+
+    /// Strings can't contain line breaks, but a backslash at the end
+    /// of a line continues the string on the next. Besides JSON's
+    /// escapes, `\v`, `\0` and `\x` escapes of two hex digits are
+    /// allowed, and any other character escapes to itself.
+    fn string() -> Parser<String> {
+        let line_break = seq("\r\n") | seq("\n") | seq("\r") | seq("\u{2028}") | seq("\u{2029}");
+        let hex2 = (one_of(b"0123456789abcdefABCDEF").exactly(2) - collect)
+            ^ |digits: String| u8::from_str_radix(&digits, 16).map(char::from);
+        // `\0` can't be followed by a digit, which would be an octal escape
+        let null = sym('0') << !one_of(b"0123456789");
+        let other = any()
+            ^ |ch: char| match ch.is_ascii_digit() || "xu\r\n\u{2028}\u{2029}".contains(ch) {
+                true => Err(()),
+                false => Ok(ch),
+            };
+        let escape = (line_break - |_| vec![])
+            | (sym('x') >> (hex2 - units))
+            | (null - |_| units('\0'))
+            | ((one_of(b"bfnrtv") - short_escape) - units)
+            | (other - units);
+        let allowed = |ch| ch != '\n' && ch != '\r';
+        quoted('"', allowed, escape.clone()) | quoted('\'', allowed, escape)
+    }
+
+    fn key() -> Parser<String> {
+        Self::string() | identifier_name()
+    }
+
+    const TRAILING: Trailing = Trailing::Allow;
+}
+
+/// Whether a character is whitespace in JSON5, which is any Unicode
+/// space separator or line break, a tab, a vertical tab, a form feed,
+/// or a byte order mark
+fn relaxed_space(ch: char) -> bool {
+    match ch {
+        '\t' | '\n' | '\x0B' | '\x0C' | '\r' | ' ' | '\u{FEFF}' => true,
+        // Unicode space separators
+        '\u{A0}' | '\u{1680}' | '\u{202F}' | '\u{205F}' | '\u{3000}' => true,
+        '\u{2000}'..='\u{200A}' => true,
+        // Unicode line breaks
+        '\u{2028}' | '\u{2029}' => true,
+        _ => false,
+    }
+}
+
+/// Consumes a token, and the whitespace around it
+fn token<D: Dialect>(symbol: &'static str) -> Parser<String> {
+    D::space() >> seq(symbol) << D::space()
+}
+
+/// Consumes one or more decimal digits
+fn digits() -> Parser<String> {
+    one_of(b"0123456789").repeat(1..) - collect
+}
+
+/// Consumes the integer part of a number, which can't have leading zeros
+fn integer() -> Parser<String> {
+    seq("0")
+        | ((one_of(b"123456789") & (one_of(b"0123456789").repeat(..) - collect))
+            - |(first, rest): (char, String)| format!("{}{}", first, rest))
+}
+
+/// Consumes the exponent of a number, and returns its text
+fn exponent() -> Parser<String> {
+    ((one_of(b"eE") >> opt(one_of(b"+-"))) & digits())
+        - |(sign, digits): (Option<char>, String)| {
+            let mut text = String::from("e");
+            text.extend(sign);
+            text + &digits
+        }
+}
+
+/// Consumes a number without a sign, and returns its text.
+/// The integer part can't have leading zeros, and a fraction
+/// or exponent needs at least one digit.
+fn unsigned() -> Parser<String> {
+    let frac = opt(sym('.') >> digits());
+    (integer() & frac & opt(exponent()))
+        - |((int, frac), exp): ((String, Option<String>), Option<String>)| {
+            let mut text = int;
+            if let Some(frac) = frac {
+                text = text + "." + &frac;
+            }
+            text + &exp.unwrap_or_default()
+        }
+}
+
+/// Consumes a JSON5 number without a sign, and returns its text.
+/// Unlike JSON, the digits before or after the decimal point
+/// can be left out, like `.5` or `5.`, but not both.
+fn decimal() -> Parser<String> {
+    let frac = sym('.') >> (opt(digits()) - |digits| digits.unwrap_or_default());
+    let mantissa = ((integer() & opt(frac))
+        - |(int, frac): (String, Option<String>)| match frac {
+            Some(frac) => int + "." + &frac,
+            None => int,
+        })
+        | ((sym('.') >> digits()) - |frac| String::from("0.") + &frac);
+    (mantissa & opt(exponent()))
+        - |(mantissa, exp): (String, Option<String>)| mantissa + &exp.unwrap_or_default()
+}
+
+/// Consumes four hex digits, and returns the UTF-16 code unit they spell
+fn hex4() -> Parser<u16> {
+    (one_of(b"0123456789abcdefABCDEF").exactly(4) - collect)
+        ^ |digits: String| u16::from_str_radix(&digits, 16)
+}
+
+/// The UTF-16 code units of a character
+fn units(ch: char) -> Vec<u16> {
+    let mut units = [0; 2];
+    ch.encode_utf16(&mut units).to_vec()
+}
+
+/// The character a single character escape like `\n` stands for
+fn short_escape(ch: char) -> char {
+    match ch {
+        'b' => '\x08',
+        'f' => '\x0C',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'v' => '\x0B',
+        ch => ch,
+    }
+}
+
+/// Consumes a string between quotes. Characters other than the quote
+/// and backslash are allowed if `allowed` is true for them, and the
+/// backslash starts either a `\u` escape or what `escape` consumes.
+/// `\u` escapes for characters outside the Basic Multilingual Plane
+/// must come in surrogate pairs.
+fn quoted(quote: char, allowed: fn(char) -> bool, escape: Parser<Vec<u16>>) -> Parser<String> {
+    let unescaped = (any()
+        ^ move |ch| match ch != quote && ch != '\\' && allowed(ch) {
+            true => Ok(ch),
+            false => Err(()),
+        })
+        % "a character that doesn't need escaping";
+    let escaped = sym('\\') >> ((sym('u') >> (hex4() - |unit| vec![unit])) | escape);
+
+    // Joining the code units back up fails on a lone surrogate
+    ((sym(quote) >> ((unescaped - units) | escaped).repeat(..) << sym(quote))
+        ^ |pieces: Vec<Vec<u16>>| String::from_utf16(&pieces.concat()))
+        % "a string"
+}
+
+/// Whether a character can start an ECMAScript identifier
+fn identifier_start(ch: char) -> bool {
+    ch.is_alphabetic() || ch == '$' || ch == '_'
+}
+
+/// Whether a character can be part of an ECMAScript identifier,
+/// including the zero width joiner and non-joiner
+fn identifier_part(ch: char) -> bool {
+    identifier_start(ch) || ch.is_alphanumeric() || ch == '\u{200C}' || ch == '\u{200D}'
+}
+
+/// Consumes an ECMAScript IdentifierName, which is how JSON5 keys
+/// are written without quotes. It starts with a letter, `$` or `_`,
+/// and goes on with those or digits. Any of them can be written as
+/// a `\u` escape. Unlike `language::identifier`, it can be any length.
+fn identifier_name() -> Parser<String> {
+    let escaped = seq("\\u") >> (hex4() ^ |unit| char::from_u32(unit.into()).ok_or(()));
+    let character = move |valid: fn(char) -> bool| {
+        let check = move |ch: char| match valid(ch) {
+            true => Ok(ch),
+            false => Err(()),
+        };
+        (any() ^ check) | (escaped.clone() ^ check)
+    };
+    ((character(identifier_start) & character(identifier_part).repeat(..))
+        - |(first, rest): (char, Vec<char>)| core::iter::once(first).chain(rest).collect())
+        % "an identifier"
+}
+
+fn array<D: Dialect>(value: Parser<JsonValue>) -> Parser<JsonValue> {
+    (token::<D>("[") >> sep_by(value, token::<D>(","), D::TRAILING) << token::<D>("]"))
+        - JsonValue::Array
+}
+
+fn object<D: Dialect>(value: Parser<JsonValue>) -> Parser<JsonValue> {
+    let member = (D::space() >> D::key() << token::<D>(":")) & value;
+    (token::<D>("{") >> sep_by(member, token::<D>(","), D::TRAILING) << token::<D>("}"))
+        - JsonValue::Object
+}
+
+/// Consumes a JSON value, and the whitespace around it.
+/// The items of arrays and objects are parsed by `nested`.
+fn value<D: Dialect>(nested: Parser<JsonValue>) -> Parser<JsonValue> {
+    (D::space()
+        >> ((seq("null") - |_| JsonValue::Null)
+            | (seq("true") - |_| JsonValue::Bool(true))
+            | (seq("false") - |_| JsonValue::Bool(false))
+            | (D::number() - JsonValue::Num)
+            | (D::string() - JsonValue::Str)
+            | array::<D>(nested.clone())
+            | object::<D>(nested))
+        << D::space())
+        % "a JSON value"
+}
+
+/// Builds the value parser of a dialect once. Arrays and objects
+/// refer back to it through a shared cell, instead of building it
+/// again for every value like `rec` would. They only hold a weak
+/// reference, so the grammar doesn't keep itself alive, and they
+/// count how deeply values are nested.
+fn grammar<D: Dialect>() -> Parser<JsonValue> {
+    let cell: Rc<OnceCell<Parser<JsonValue>>> = Rc::new(OnceCell::new());
+    let weak = Rc::downgrade(&cell);
+    let depth = Cell::new(0);
+    let nested = Parser::new(
+        move |ctx: &mut Context, offset: usize| {
+            if depth.get() == MAX_DEPTH {
+                let expected = format!("at most {} nested arrays and objects", MAX_DEPTH);
+                // Nothing else could parse the value at this depth
+                return unexpected(ctx.input(), expected, offset).map_err(|e| e.fatal());
+            }
+            let cell = weak.upgrade().expect("the grammar is used while it's alive");
+            depth.set(depth.get() + 1);
+            let result = cell.get().expect("the grammar is built").parse_internal(ctx, offset);
+            depth.set(depth.get() - 1);
+            result
+        },
+        "a JSON value",
+    );
+    let expectation = cell.get_or_init(|| value::<D>(nested)).expectation.clone();
+    Parser::new(
+        move |ctx: &mut Context, offset: usize| {
+            cell.get().expect("the grammar is built").parse_internal(ctx, offset)
+        },
+        expectation,
+    )
+}
+
+/// Consumes a whole JSON document, which is a single value
+/// with optional whitespace around it, and nothing else.
+/// Arrays and objects can be nested `MAX_DEPTH` deep.
+pub fn json() -> Parser<JsonValue> {
+    grammar::<Strict>() << eof()
+}
+
+/// Consumes a whole JSON5 document, like a config file. This is
+/// JSON with `//` and `/* */` comments, identifiers as keys,
+/// single quoted strings that can span lines and have more escapes,
+/// hex numbers, numbers like `.5` and `5.`, `Infinity` and `NaN`,
+/// and trailing commas in arrays and objects.
+/// Arrays and objects can be nested `MAX_DEPTH` deep.
+pub fn json5() -> Parser<JsonValue> {
+    grammar::<Relaxed>() << eof()
+}
@@ -0,0 +1,246 @@
+// This module contains parsers for indentation sensitive syntax,
+// where blocks are made of lines indented by the same amount,
+// like Python or YAML.
+
+use crate::{unexpected, Context, Error, Parser};
+
+/// We need alloc!
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// How indentation is measured. Spaces are one column wide,
+/// and tabs move to the next multiple of the tab width.
+/// The functions in this module use the default tab width of 8.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Indent {
+    tab_width: usize,
+}
+
+impl Default for Indent {
+    fn default() -> Self {
+        Self { tab_width: 8 }
+    }
+}
+
+/// Where the next line that isn't blank starts
+enum Next {
+    /// There is more input on this line, starting at this offset
+    Content(usize),
+    /// Only blank lines are left
+    End,
+    /// The indentation of the line, and the offset after it
+    Line(usize, usize),
+}
+
+impl Indent {
+    /// Measure tabs with this tab width
+    pub fn tabs(tab_width: usize) -> Self {
+        Self {
+            tab_width: tab_width.max(1),
+        }
+    }
+
+    /// The indentation of the line at an offset,
+    /// and the offset of the first character after it
+    fn measure(&self, s: &str, offset: usize) -> (usize, usize) {
+        let line_start = s[..offset].rfind('\n').map_or(0, |n| n + 1);
+        let mut width = 0;
+        for (i, ch) in s[line_start..].char_indices() {
+            match ch {
+                ' ' => width += 1,
+                '\t' => width += self.tab_width - width % self.tab_width,
+                _ => return (width, line_start + i),
+            }
+        }
+        (width, s.len())
+    }
+
+    /// Find the next line that isn't blank after the rest of this line
+    fn next_line(&self, ctx: &mut Context, offset: usize) -> Next {
+        let s = ctx.input();
+        let rest = &s[offset..];
+        let trimmed = rest.trim_start_matches([' ', '\t', '\r']);
+        if !trimmed.is_empty() && !trimmed.starts_with('\n') {
+            return Next::Content(s.len() - trimmed.len());
+        }
+
+        let content = rest.trim_start_matches([' ', '\t', '\r', '\n']);
+        if content.is_empty() {
+            // More lines could be on the way
+            ctx.reached_end();
+            return Next::End;
+        }
+        let (width, end) = self.measure(s, s.len() - content.len());
+        Next::Line(width, end)
+    }
+
+    /// Whether an offset only has indentation before it on its line
+    fn at_line_start(&self, s: &str, offset: usize) -> bool {
+        self.measure(s, offset).1 >= offset
+    }
+
+    /// The error for a line that isn't indented as expected
+    fn error<T>(ctx: &Context, next: Next, expected: impl ToString) -> Result<T, Error> {
+        let s = ctx.input();
+        let offset = match next {
+            Next::Line(_, offset) => offset,
+            Next::End => s.len(),
+            // The line should have ended here
+            Next::Content(offset) => {
+                return unexpected(s, "the end of the line", offset)
+            }
+        };
+        unexpected(s, expected, offset)
+    }
+
+    /// Consumes the end of this line and any blank lines after it,
+    /// and the indentation of the next line, which must be the same as
+    /// the indentation of the line this parser starts on.
+    /// The output is the indentation.
+    pub fn same(self) -> Parser<usize> {
+        Parser::new(
+            move |ctx: &mut Context, offset: usize| {
+                let (current, _) = self.measure(ctx.input(), offset);
+                match self.next_line(ctx, offset) {
+                    Next::Line(width, end) if width == current => Ok((width, end)),
+                    next => Self::error(ctx, next, format!("a line indented {} columns", current)),
+                }
+            },
+            "a line with the same indentation",
+        )
+    }
+
+    /// Consumes the end of this line and any blank lines after it,
+    /// and the indentation of the next line, which must be deeper than
+    /// the indentation of the line this parser starts on.
+    /// This is the INDENT token of languages like Python.
+    /// The output is the new indentation.
+    pub fn deeper(self) -> Parser<usize> {
+        Parser::new(
+            move |ctx: &mut Context, offset: usize| {
+                let (current, _) = self.measure(ctx.input(), offset);
+                match self.next_line(ctx, offset) {
+                    Next::Line(width, end) if width > current => Ok((width, end)),
+                    next => Self::error(
+                        ctx,
+                        next,
+                        format!("a line indented more than {} columns", current),
+                    ),
+                }
+            },
+            "a line with deeper indentation",
+        )
+    }
+
+    /// Consumes nothing, but succeeds if the next line that isn't blank
+    /// is indented less than the line this parser starts on, or if there
+    /// are no more lines. This is the DEDENT token of languages like Python.
+    pub fn dedent(self) -> Parser<()> {
+        Parser::new(
+            move |ctx: &mut Context, offset: usize| {
+                let (current, _) = self.measure(ctx.input(), offset);
+                match self.next_line(ctx, offset) {
+                    Next::End => Ok(((), offset)),
+                    Next::Line(width, _) if width < current => Ok(((), offset)),
+                    next => Self::error(
+                        ctx,
+                        next,
+                        format!("a line indented less than {} columns", current),
+                    ),
+                }
+            },
+            "a line with less indentation",
+        )
+    }
+
+    /// Consumes a block of one or more items, each starting on its own line,
+    /// and all indented the same. The block starts on the next line that
+    /// isn't blank, which must be indented deeper than the line this parser
+    /// starts on. If this parser starts at the beginning of a line,
+    /// like at the start of a file, the block can start on that line,
+    /// at any indentation.
+    ///
+    /// The block ends at the first line that is indented differently,
+    /// or that isn't an item. Items can contain blocks of their own.
+    pub fn block<T>(self, item: Parser<T>) -> Parser<Vec<T>>
+    where
+        T: 'static + Clone,
+    {
+        let expectation = format!("an indented block of {}", item.expectation);
+        Parser::new(
+            move |ctx: &mut Context, offset: usize| {
+                let s = ctx.input();
+                let (width, mut end) = if self.at_line_start(s, offset) {
+                    match self.next_line(ctx, offset) {
+                        Next::Line(width, end) => (width, end),
+                        // This line has the first item
+                        Next::Content(_) => self.measure(s, offset),
+                        Next::End => return Self::error(ctx, Next::End, "an indented block"),
+                    }
+                } else {
+                    self.deeper().parse_internal(ctx, offset)?
+                };
+
+                let (first, e) = item.parse_internal(ctx, end)?;
+                let mut items = vec![first];
+                end = e;
+                loop {
+                    match self.next_line(ctx, end) {
+                        Next::Line(w, start) if w == width => match ctx.alternative(&item, start) {
+                            Ok((t, e)) => {
+                                items.push(t);
+                                end = e;
+                            }
+                            Err(e) if e.is_fatal() => return Err(e),
+                            Err(e) => {
+                                ctx.record(e);
+                                break;
+                            }
+                        },
+                        // A line indented deeper that the last item didn't
+                        // consume can't belong to anything else
+                        next @ Next::Line(w, _) if w > width => {
+                            let expected = format!("a line indented {} columns", width);
+                            ctx.record(Self::error::<()>(ctx, next, expected).unwrap_err());
+                            break;
+                        }
+                        _ => break,
+                    }
+                }
+                Ok((items, end))
+            },
+            expectation,
+        )
+    }
+}
+
+/// Consumes a block of one or more items, each on its own line,
+/// all indented the same, and deeper than the line the block starts on.
+/// See `Indent::block`.
+pub fn indented_block<T>(item: Parser<T>) -> Parser<Vec<T>>
+where
+    T: 'static + Clone,
+{
+    Indent::default().block(item)
+}
+
+/// Consumes the end of this line, and the indentation of the next line
+/// that isn't blank, which must be the same as this line's.
+/// See `Indent::same`.
+pub fn same_indent() -> Parser<usize> {
+    Indent::default().same()
+}
+
+/// Consumes the end of this line, and the indentation of the next line
+/// that isn't blank, which must be deeper than this line's.
+/// See `Indent::deeper`.
+pub fn deeper_indent() -> Parser<usize> {
+    Indent::default().deeper()
+}
+
+/// Consumes nothing, but succeeds if the next line that isn't blank
+/// is indented less than this line, or there are no more lines.
+/// See `Indent::dedent`.
+pub fn dedent() -> Parser<()> {
+    Indent::default().dedent()
+}
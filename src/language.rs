@@ -4,7 +4,7 @@
 
 /// Import necessary atoms
 use crate::{
-    atoms::{if_take, list, none_of, one_of, opt, seq, seq_no_ws, space, sym},
+    atoms::{if_take, none_of, one_of, opt, sep_by, seq, seq_no_ws, space, sym, Trailing},
     transform::{collect, to_string},
     Parser,
 };
@@ -28,8 +28,7 @@ pub fn numeral() -> Parser<char> {
 
 /// Consumes an alphanumeric character
 pub fn alphanumeric() -> Parser<char> {
-    alpha() | numeral()
-        % "an alphanumeric character"
+    (alpha() | numeral()) % "an alphanumeric character"
 }
 
 /// Consumes a punctuation character
@@ -56,7 +55,7 @@ pub fn token() -> Parser<String> {
 
 /// Consumes an alphanumeric identifier
 pub fn identifier() -> Parser<String> {
-    (alpha().is() >> (((alphanumeric() | sym('_')) * (1..31)) - collect))
+    (alpha().is() >> (((alphanumeric() | sym('_')) * (1..=31)) - collect))
         % "an identifier"
 }
 
@@ -109,12 +108,14 @@ pub fn number() -> Parser<String> {
         % "a number"
 }
 
-/// Consumes an array of items
+/// Consumes an array of comma separated items,
+/// with a trailing comma handled by the policy
 pub fn array<T: 'static + Clone>(
     begin: &'static str,
     item: Parser<T>,
     end: &'static str,
+    trailing: Trailing,
 ) -> Parser<Vec<T>> {
-    seq_no_ws(begin) >> list(item.clone(), seq_no_ws(",")) << seq_no_ws(end)
+    (seq_no_ws(begin) >> sep_by(item.clone(), seq_no_ws(","), trailing) << seq_no_ws(end))
         % format!("An array of 0 or more {}(s)", item.expectation)
 }
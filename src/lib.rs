@@ -3,9 +3,14 @@
 #[macro_use]
 extern crate alloc;
 
+/// Reading streams from `std::io` needs std
+#[cfg(feature = "std")]
+extern crate std;
+
 /// This module contains the Parser and Error types which
 /// contain the minimal logic for implementing the atomic
-/// parser combinators.
+/// parser combinators, and the Input trait for the text,
+/// bytes, and tokens they can parse.
 mod parser;
 pub use parser::*;
 
@@ -21,6 +26,25 @@ pub mod language;
 /// as email address parsers, version number parsers, etc.
 pub mod basic;
 
+/// This module contains a Pratt parser, which is useful for
+/// parsing expressions with prefix, infix, postfix, and ternary
+/// operators that have different precedences.
+pub mod expr;
+
+/// This module contains parsers for whole data formats,
+/// such as JSON, that are ready to use.
+pub mod formats;
+
+/// This module is useful for languages where indentation matters,
+/// such as Python or YAML, with parsers for indented blocks and
+/// the INDENT and DEDENT tokens of the offside rule.
+pub mod indent;
+
+/// This module contains the Stream driver, which parses items
+/// from input that arrives a chunk at a time, such as a log
+/// file too large to read into memory at once.
+pub mod stream;
+
 /// This module is useful for transforming the output of a parser
 /// into something useful. An example of this is converting a
 /// Vec<char> into a String.
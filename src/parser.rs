@@ -3,10 +3,14 @@
 /// parser combinators.
 
 /// Required modules and traits from core
+use core::any::{Any, TypeId};
 use core::fmt;
 use core::ops::Bound::*;
-use core::ops::{BitAnd, BitOr, BitXor, Mul, Not, RangeBounds, Rem, Shl, Shr, Sub};
+use core::ops::{BitAnd, BitOr, BitXor, Mul, Not, Range, RangeBounds, Rem, Shl, Shr, Sub};
+use core::sync::atomic::{AtomicUsize, Ordering};
 
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
 use alloc::string::{String, ToString};
 use alloc::sync::Arc;
 /// We need alloc!
@@ -14,127 +18,1148 @@ use alloc::vec::Vec;
 
 /// This struct is the Err result when parsing.
 /// It contains a string representing:
-/// The actual input received
+/// The actual input received, or nothing at the end of the input
 /// The expected input
-/// And the remaining, unparsed input
-#[derive(Clone, PartialEq)]
+/// And the position in the input where parsing failed:
+/// the byte offset, and the 1-based line and column.
+/// When several alternatives fail at the same position,
+/// the error expects any one of them.
+/// An error can also carry a label, which is shown next
+/// to the failing input when the error is rendered.
+/// A fatal error stops any backtracking combinator from
+/// trying another alternative, see `Parser::commit`.
+#[derive(Clone)]
 pub struct Error {
-    actual: String,
-    expected: String,
-    remaining_input: String,
+    /// None at the end of the input
+    actual: Option<String>,
+    expected: Vec<String>,
+    offset: usize,
+    line: usize,
+    column: usize,
+    label: Option<String>,
+    fatal: bool,
 }
 
 impl Error {
-    pub fn new<T>(
-        actual: impl ToString,
-        expected: impl ToString,
-        remaining_input: impl ToString,
-    ) -> Result<T, Self> {
+    /// Create an error at a byte offset into the input.
+    /// The line and column are filled in by `Parser::parse`,
+    /// or by calling `locate` with the original input.
+    pub fn new<T>(actual: impl ToString, expected: impl ToString, offset: usize) -> Result<T, Self> {
         Err(Self {
-            actual: actual.to_string(),
-            expected: expected.to_string(),
-            remaining_input: remaining_input.to_string(),
+            actual: Some(actual.to_string()),
+            expected: vec![expected.to_string()],
+            offset,
+            line: 0,
+            column: 0,
+            label: None,
+            fatal: false,
         })
     }
+
+    /// Create an error for reaching the end of the input at a byte offset
+    pub fn end<T>(expected: impl ToString, offset: usize) -> Result<T, Self> {
+        Self::new(String::new(), expected, offset).map_err(|mut e| {
+            e.actual = None;
+            e
+        })
+    }
+
+    /// Attach a label to this error, such as "this object is never closed"
+    pub fn with_label(mut self, label: impl ToString) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Make this error fatal. A fatal error isn't backtracked from
+    /// by `or`, `opt` or `repeat`, so it is reported where it happened.
+    pub fn fatal(mut self) -> Self {
+        self.fatal = true;
+        self
+    }
+
+    /// Compute the line and column of this error from the
+    /// original input that was being parsed.
+    pub fn locate<I>(mut self, input: &I) -> Self
+    where
+        I: Input + ?Sized,
+    {
+        (self.line, self.column) = input.locate(self.offset);
+        self
+    }
+
+    /// Combine two errors for the same input, keeping the one
+    /// that got furthest. If both failed at the same offset,
+    /// the result expects anything either of them expected.
+    pub fn merge(mut self, other: Self) -> Self {
+        if other.offset > self.offset {
+            return other;
+        }
+        if other.offset == self.offset {
+            self.expected.extend(other.expected);
+            // Sort so the order alternatives were tried in doesn't matter
+            self.expected.sort();
+            self.expected.dedup();
+            self.fatal |= other.fatal;
+        }
+        self
+    }
+
+    /// The input that was actually found,
+    /// which is empty at the end of the input
+    pub fn actual(&self) -> &str {
+        self.actual.as_deref().unwrap_or("")
+    }
+
+    /// Whether parsing failed at the end of the input
+    pub fn at_end(&self) -> bool {
+        self.actual.is_none()
+    }
+
+    /// The inputs that were expected. Any one of these
+    /// would have let parsing continue.
+    pub fn expected(&self) -> &[String] {
+        &self.expected
+    }
+
+    /// The byte offset into the input where parsing failed
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The 1-based line where parsing failed
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The 1-based column where parsing failed
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// The label attached to this error, if any
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Move a located error in part of the input to where it is in all
+    /// of the input, given the offset, line, and column the part starts at
+    pub(crate) fn shift(mut self, offset: usize, line: usize, column: usize) -> Self {
+        if self.line == 1 {
+            self.column += column - 1;
+        }
+        self.line += line - 1;
+        self.offset += offset;
+        self
+    }
+
+    /// Whether parsing had committed to an alternative when this error happened
+    pub fn is_fatal(&self) -> bool {
+        self.fatal
+    }
+
+    /// Render this error like a compiler diagnostic: the message,
+    /// the line of the source it occurred on, and a caret under
+    /// the input that failed to parse.
+    ///
+    /// ```text
+    /// error: Expected one of `,`, `}` but found `"`
+    ///  --> 1:9
+    ///   |
+    /// 1 | {"a": 1 "b": 2}
+    ///   |         ^
+    /// ```
+    ///
+    /// The source must be the input this error came from.
+    pub fn render(&self, source: &str) -> String {
+        let located = self.clone().locate(source);
+
+        // Find the line the error is on, without its line ending
+        let line_start = source[..self.offset].rfind('\n').map_or(0, |n| n + 1);
+        let line = source[line_start..].split('\n').next().unwrap_or("");
+        let line = line.strip_suffix('\r').unwrap_or(line);
+
+        // Indent the caret with the same tabs as the source line,
+        // so that it still lines up when the line is indented with tabs.
+        let indent = line
+            .chars()
+            .take(located.column - 1)
+            .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+
+        // Underline as much of the actual input as fits on this line,
+        // and at least one character so EOF is still pointed at.
+        let remaining = line.chars().count().saturating_sub(located.column - 1);
+        let width = match &self.actual {
+            Some(actual) => actual.chars().count().min(remaining).max(1),
+            None => 1,
+        };
+
+        let number = located.line.to_string();
+        let gutter = " ".repeat(number.len());
+
+        let mut result = format!(
+            "error: {}\n{}--> {}:{}\n{} |\n{} | {}\n{} | {}{}",
+            Message(self),
+            gutter,
+            located.line,
+            located.column,
+            gutter,
+            number,
+            line,
+            gutter,
+            indent,
+            "^".repeat(width)
+        );
+        if let Some(label) = &self.label {
+            result += " ";
+            result += label;
+        }
+        result
+    }
+}
+
+/// The description of an error, without its position
+struct Message<'a>(&'a Error);
+
+impl fmt::Display for Message<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let error = self.0;
+        if error.expected.len() == 1 {
+            write!(f, "Expected `{}`", error.expected[0])?;
+        } else {
+            write!(f, "Expected one of ")?;
+            for (i, expected) in error.expected.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "`{}`", expected)?;
+            }
+        }
+        let actual = match &error.actual {
+            Some(actual) => actual,
+            None => return write!(f, " but found the end of the input"),
+        };
+        // Escape newlines and the like so the message stays on one line
+        write!(f, " but found `")?;
+        for ch in actual.chars() {
+            if ch.is_control() {
+                write!(f, "{}", ch.escape_debug())?;
+            } else {
+                write!(f, "{}", ch)?;
+            }
+        }
+        write!(f, "`")
+    }
+}
+
+/// The line and column are derived from the offset,
+/// so they are not compared. This lets an error built with
+/// `Error::new` equal the same error returned by `Parser::parse`.
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        self.actual == other.actual
+            && self.expected == other.expected
+            && self.offset == other.offset
+            && self.label == other.label
+            && self.fatal == other.fatal
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{} at {}:{}", Message(self), self.line, self.column)
+    }
 }
 
 /// Needed for assertions and general debugging
 impl fmt::Debug for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(
-            f,
-            "Expected `{}` but found `{}` when parsing `{}`",
-            self.expected,
-            self.actual,
-            self.remaining_input.replace("\n", "")
-        )
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// The input a Parser can parse. Offsets into the input count bytes
+/// for a `str`, and elements for a slice, so a `[u8]` is parsed byte
+/// by byte, and a `[Tok]` from a lexer is parsed token by token.
+pub trait Input {
+    /// The offset of the end of the input
+    fn len(&self) -> usize;
+
+    /// Whether there is no input at all
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The offset after the item at `offset`,
+    /// or None at the end of the input
+    fn next(&self, offset: usize) -> Option<usize>;
+
+    /// Describe part of the input, for the actual input of an error
+    fn describe(&self, range: Range<usize>) -> String;
+
+    /// The 1-based line and column of an offset
+    fn locate(&self, offset: usize) -> (usize, usize);
+}
+
+/// Text is parsed by byte offset, but always a whole character at a time
+impl Input for str {
+    fn len(&self) -> usize {
+        str::len(self)
+    }
+
+    fn next(&self, offset: usize) -> Option<usize> {
+        let ch = self[offset..].chars().next()?;
+        Some(offset + ch.len_utf8())
+    }
+
+    fn describe(&self, range: Range<usize>) -> String {
+        self[range].to_string()
+    }
+
+    /// Columns count characters, not bytes
+    fn locate(&self, offset: usize) -> (usize, usize) {
+        let before = &self[..offset];
+        let line_start = match before.rfind('\n') {
+            Some(n) => n + 1,
+            None => 0,
+        };
+        let line = before.matches('\n').count() + 1;
+        let column = before[line_start..].chars().count() + 1;
+        (line, column)
+    }
+}
+
+/// Slices are parsed an element at a time. This covers byte slices
+/// as well as tokens. Tokens don't have to be Debug, so the input is
+/// described by position, but `tok`, `tok_if` and `bytes` show the
+/// tokens they fail on. Slices don't have lines, so the column is the
+/// offset plus one.
+impl<Tok> Input for [Tok]
+where
+    Tok: Clone + PartialEq,
+{
+    fn len(&self) -> usize {
+        <[Tok]>::len(self)
+    }
+
+    fn next(&self, offset: usize) -> Option<usize> {
+        match offset < self.len() {
+            true => Some(offset + 1),
+            false => None,
+        }
+    }
+
+    fn describe(&self, range: Range<usize>) -> String {
+        match range.len() {
+            1 => format!("token {}", range.start),
+            _ => format!("tokens {}..{}", range.start, range.end),
+        }
+    }
+
+    fn locate(&self, offset: usize) -> (usize, usize) {
+        (1, offset + 1)
     }
 }
 
 /// The Output type represents the output of a parser.
-/// Ok(T, String) result represents successfully parsed & lexed input.
+/// Ok(T, usize) result represents successfully parsed & lexed input.
 /// The T type represents the consumed and lexed input,
-/// and the String represents the remaining input.
-pub type Output<T> = Result<(T, String), Error>;
+/// and the usize is the byte offset of the remaining input.
+/// Parsers never copy the input, they only move this offset forward.
+pub type Output<T> = Result<(T, usize), Error>;
+
+/// The function a Parser wraps. It receives the context of the
+/// current parse and the offset to start parsing from.
+type ParseFn<T, I> = dyn Fn(&mut Context<I>, usize) -> Output<T>;
+
+/// Identifies a memoized parser in the memo table.
+/// Parsers made by `memo_rec` and `left_rec` are identified by the type
+/// of the function that builds them, so every use of that rule shares
+/// the same results. Every function has its own type, unlike its address,
+/// which isn't guaranteed to be unique or the same everywhere it's used.
+/// Other memoized parsers are numbered when `Parser::memo` is called.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum MemoKey {
+    Parser(usize),
+    Rule(TypeId),
+}
+
+impl MemoKey {
+    /// The key of the rule a function builds. The function must be a
+    /// function item or a closure that captures nothing, since a function
+    /// pointer or a capturing closure has the same type for many rules.
+    pub(crate) fn rule<F: 'static>(_: &F) -> Self {
+        const {
+            assert!(
+                core::mem::size_of::<F>() == 0,
+                "a rule must be a function, not a function pointer or a capturing closure"
+            )
+        };
+        MemoKey::Rule(TypeId::of::<F>())
+    }
+}
+
+/// The number of the next parser made with `Parser::memo`
+static NEXT_MEMO: AtomicUsize = AtomicUsize::new(0);
+
+/// A memoized result of a parser with output T. Along with the result,
+/// this stores everything else the parser did to the context while
+/// producing it, so using the result again has the same effect
+/// as parsing again would.
+#[derive(Clone)]
+struct Memo<T> {
+    result: Output<T>,
+    /// The furthest error recorded
+    recorded: Option<Error>,
+    /// Whether a `cut` was passed
+    cut: bool,
+    /// The errors that were recovered from
+    recovered: Vec<Error>,
+}
+
+impl<T> Memo<T> {
+    /// A memoized result that didn't do anything else
+    fn seed(result: Output<T>) -> Self {
+        Self {
+            result,
+            recorded: None,
+            cut: false,
+            recovered: vec![],
+        }
+    }
+}
+
+/// The state given to `Parser::parse_with_state`, which can be
+/// cloned without knowing its type so it can be rolled back
+trait State: Any {
+    fn snapshot(&self) -> Box<dyn State>;
+    fn as_any(&mut self) -> &mut dyn Any;
+}
+
+impl<S: Any + Clone> State for S {
+    fn snapshot(&self) -> Box<dyn State> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// The parts of the context a memoized parser changes, taken out of
+/// the context before running it so those changes can be memoized
+struct Outer {
+    furthest: Option<Error>,
+    cut: bool,
+    /// How many errors had been recovered from
+    recovered: usize,
+}
+
+/// The Context holds everything about a single call to `Parser::parse`
+/// that isn't the position: the entire input, the furthest failure
+/// any backtracking combinator has seen so far, whether the current
+/// alternative has been committed to, the errors that have been
+/// recovered from, the user's state, and the results of memoized parsers.
+pub struct Context<'a, I: ?Sized = str> {
+    input: &'a I,
+    furthest: Option<Error>,
+    /// Whether a `cut` was passed in the alternative being parsed
+    cut: bool,
+    /// Whether any parser looked at the end of the input
+    reached_end: bool,
+    /// Errors that parsing recovered from, in the order they happened
+    recovered: Vec<Error>,
+    /// The state given to `Parser::parse_with_state`
+    state: Option<Box<dyn State>>,
+    /// Memoized results, ordered by offset first
+    /// so that all the results at an offset can be found quickly.
+    memo: BTreeMap<(usize, MemoKey), Box<dyn Any>>,
+    /// The left recursive rules that are currently growing their results
+    growing: Vec<(usize, MemoKey)>,
+}
+
+impl<'a, I> Context<'a, I>
+where
+    I: Input + ?Sized + 'static,
+{
+    pub fn new(input: &'a I) -> Self {
+        Self {
+            input,
+            furthest: None,
+            cut: false,
+            reached_end: false,
+            recovered: vec![],
+            state: None,
+            memo: BTreeMap::new(),
+            growing: vec![],
+        }
+    }
+
+    /// The entire input given to `Parser::parse`
+    pub fn input(&self) -> &'a I {
+        self.input
+    }
+
+    /// Remember an error that a combinator recovered from,
+    /// such as the failed branch of an `or`. If parsing fails
+    /// later on, the furthest of these errors is reported.
+    pub fn record(&mut self, error: Error) {
+        self.furthest = Some(match self.furthest.take() {
+            Some(furthest) => furthest.merge(error),
+            None => error,
+        });
+    }
+
+    /// Merge an error with the furthest error recorded so far.
+    /// A fatal error is only merged with errors at the same offset,
+    /// so that it is reported where it happened.
+    pub fn furthest(&mut self, error: Error) -> Error {
+        match self.furthest.take() {
+            Some(furthest) if error.fatal && furthest.offset != error.offset => error,
+            Some(furthest) => furthest.merge(error),
+            None => error,
+        }
+    }
+
+    /// Log an error that parsing recovered from by skipping some input.
+    /// `Parser::parse_recovering` returns every error logged like this.
+    /// If the alternative the error happened in fails anyway,
+    /// the error is forgotten.
+    pub fn recover(&mut self, error: Error) {
+        self.recovered.push(error);
+    }
+
+    /// The state given to `Parser::parse_with_state`.
+    /// Changes to the state are undone when parsing backtracks.
+    /// This panics if there is no state, or if it isn't an S.
+    pub fn state<S: Any>(&mut self) -> &mut S {
+        let state = self
+            .state
+            .as_deref_mut()
+            .expect("the state is only available to parse_with_state");
+        state
+            .as_any()
+            .downcast_mut()
+            .expect("the state is a different type than the parser expects")
+    }
+
+    /// Note that a parser looked at the end of the input, like an atom
+    /// that failed because there was nothing left, or one that matched
+    /// only the start of what it expected. When the input is part of a
+    /// stream, more input may be on the way, so the parser could succeed
+    /// or fail differently once it arrives. Atoms must call this whenever
+    /// their result depends on where the input ends.
+    pub fn reached_end(&mut self) {
+        self.reached_end = true;
+    }
+
+    /// Commit to the alternative being parsed. If it fails from here
+    /// on, its error is fatal.
+    pub(crate) fn cut(&mut self) {
+        self.cut = true;
+    }
+
+    /// Run one alternative of a backtracking combinator, like either side
+    /// of an `or`. If the alternative fails after passing a `cut`, its
+    /// error is fatal. A cut only commits to the innermost alternative
+    /// it is in, so this restores whether the outer one was committed to.
+    /// Combinators should not backtrack from fatal errors.
+    pub fn alternative<T>(&mut self, parser: &Parser<T, I>, offset: usize) -> Output<T>
+    where
+        T: 'static + Clone,
+    {
+        self.attempt(|ctx| parser.parse_internal(ctx, offset))
+    }
+
+    /// Run one alternative like `alternative`, but if it fails, return
+    /// the error `Parser::parse` would report for it instead of recording
+    /// anything. This is the error that recovering from the failure logs.
+    pub(crate) fn reported<T>(&mut self, parser: &Parser<T, I>, offset: usize) -> Output<T>
+    where
+        T: 'static + Clone,
+    {
+        let outer = self.furthest.take();
+        let result = self.alternative(parser, offset);
+        let result = result.map_err(|e| self.furthest(e));
+        let inner = core::mem::replace(&mut self.furthest, outer);
+        if let Some(e) = inner {
+            self.record(e);
+        }
+        result
+    }
+
+    /// Like `alternative`, for anything that parses
+    pub(crate) fn attempt<T>(&mut self, parse: impl FnOnce(&mut Self) -> Output<T>) -> Output<T> {
+        let outer = core::mem::replace(&mut self.cut, false);
+        let recovered = self.recovered.len();
+        let state = self.state.as_deref().map(|s| s.snapshot());
+        let result = parse(self);
+        let cut = core::mem::replace(&mut self.cut, outer);
+        result.map_err(|e| {
+            // Whatever we recovered from in here is backtracked from too,
+            // and whatever we changed the state to is rolled back
+            self.recovered.truncate(recovered);
+            if state.is_some() {
+                self.state = state;
+            }
+            match cut {
+                true => e.fatal(),
+                false => e,
+            }
+        })
+    }
+
+    /// Run a parser without keeping any errors it records.
+    /// Lookahead never consumes input, so whatever it backtracked
+    /// from can't explain a failure later on. It can't commit
+    /// to anything or change the state either, so cuts and
+    /// changes to the state inside it are undone.
+    pub(crate) fn lookahead<T>(&mut self, parser: &Parser<T, I>, offset: usize) -> Output<T>
+    where
+        T: 'static + Clone,
+    {
+        let outer = self.furthest.take();
+        let cut = self.cut;
+        let recovered = self.recovered.len();
+        let state = self.state.as_deref().map(|s| s.snapshot());
+        let result = parser.parse_internal(self, offset);
+        self.furthest = outer;
+        self.cut = cut;
+        self.recovered.truncate(recovered);
+        if state.is_some() {
+            self.state = state;
+        }
+        result
+    }
+
+    /// Look up the memoized result of a parser at an offset,
+    /// and record the errors it recorded when it was first run.
+    fn remembered<T>(&mut self, key: MemoKey, offset: usize) -> Option<Output<T>>
+    where
+        T: 'static + Clone,
+    {
+        let memo = self.memo.get(&(offset, key))?.downcast_ref::<Memo<T>>()?;
+        let memo = memo.clone();
+        if let Some(e) = memo.recorded {
+            self.record(e);
+        }
+        self.cut |= memo.cut;
+        self.recovered.extend(memo.recovered);
+        Some(memo.result)
+    }
+
+    /// Take the parts of the context a memoized parser changes
+    fn enter(&mut self) -> Outer {
+        Outer {
+            furthest: self.furthest.take(),
+            cut: core::mem::take(&mut self.cut),
+            recovered: self.recovered.len(),
+        }
+    }
+
+    /// Memoize the result of a parser, along with the errors recorded since
+    /// `outer` was taken from the context. Those errors are then recorded
+    /// along with `outer`, as if they were never taken out. The same goes
+    /// for whether a cut was passed, and the errors recovered from.
+    fn remember<T>(&mut self, key: MemoKey, offset: usize, result: &Output<T>, outer: Outer)
+    where
+        T: 'static + Clone,
+    {
+        let recorded = self.furthest.take();
+        self.furthest = outer.furthest;
+        if let Some(e) = recorded.clone() {
+            self.record(e);
+        }
+        let cut = core::mem::replace(&mut self.cut, outer.cut);
+        self.cut |= cut;
+
+        let memo = Memo {
+            result: result.clone(),
+            recorded,
+            cut,
+            recovered: self.recovered[outer.recovered..].to_vec(),
+        };
+        self.memo.insert((offset, key), Box::new(memo));
+    }
+
+    /// Run a parser at an offset, unless it has already been run there.
+    /// In that case, the result from last time is returned instead.
+    fn memoized<T>(&mut self, key: MemoKey, parser: &Parser<T, I>, offset: usize) -> Output<T>
+    where
+        T: 'static + Clone,
+    {
+        if let Some(result) = self.remembered(key, offset) {
+            return result;
+        }
+
+        let outer = self.enter();
+        let result = parser.parse_internal(self, offset);
+        self.remember(key, offset, &result, outer);
+        result
+    }
+
+    /// Run a left recursive rule at an offset by growing a seed.
+    /// The rule's result is first memoized as a failure, so that when the
+    /// rule calls itself at the same offset, that call fails and the rule
+    /// falls back on an alternative that doesn't recurse. The rule is then
+    /// run again and again with its last result memoized, each time
+    /// consuming a little more input, until it stops consuming more.
+    /// This makes `expr := expr '+' term | term` terminate,
+    /// and associate to the left.
+    pub(crate) fn grow<T>(
+        &mut self,
+        key: MemoKey,
+        rule: impl Fn() -> Parser<T, I>,
+        offset: usize,
+    ) -> Output<T>
+    where
+        T: 'static + Clone,
+    {
+        if let Some(result) = self.remembered(key, offset) {
+            return result;
+        }
+
+        let outer = self.enter();
+        // The seed doesn't expect anything, so it never shows up in errors
+        let seed = Error {
+            actual: peek(self.input, offset),
+            expected: vec![],
+            offset,
+            line: 0,
+            column: 0,
+            label: None,
+            fatal: false,
+        };
+        self.memo.insert((offset, key), Box::new(Memo::<T>::seed(Err(seed))));
+        self.growing.push((offset, key));
+
+        let rule = rule();
+        let mut grown: Option<(T, usize)> = None;
+        // Only the errors recovered from while growing the result are kept
+        let mut recovered = vec![];
+        let result = loop {
+            let result = rule.parse_internal(self, offset);
+            let attempt = self.recovered.split_off(outer.recovered);
+            match (result, &grown) {
+                // The first success, or one that consumed more than the last
+                (Ok((value, end)), None) => {
+                    grown = Some((value, end));
+                    recovered = attempt;
+                }
+                (Ok((value, end)), Some((_, last))) if end > *last => {
+                    grown = Some((value, end));
+                    recovered = attempt;
+                }
+                // The seed can't grow any further
+                (Ok(_), Some(_)) => break Ok(grown.unwrap()),
+                // A fatal error can't be backtracked from
+                (Err(e), _) if e.fatal => break Err(e),
+                (Err(e), Some(_)) => {
+                    self.record(e);
+                    break Ok(grown.unwrap());
+                }
+                (Err(e), None) => break Err(e),
+            }
+
+            // Results at this offset may have used the old seed, so they
+            // are forgotten. Rules that are still growing keep their seeds.
+            let stale = self
+                .memo
+                .range((offset, MemoKey::Parser(0))..)
+                .map(|(k, _)| *k)
+                .take_while(|(o, _)| *o == offset)
+                .filter(|k| !self.growing.contains(k))
+                .collect::<Vec<_>>();
+            for k in stale {
+                self.memo.remove(&k);
+            }
+
+            let seed = Memo::seed(Ok(grown.clone().unwrap()));
+            self.memo.insert((offset, key), Box::new(seed));
+        };
+
+        self.recovered.extend(recovered);
+        self.growing.pop();
+        self.remember(key, offset, &result, outer);
+        result
+    }
+}
+
+/// Describes the item at `offset` in the input,
+/// or returns None at the end of the input
+pub(crate) fn peek<I>(input: &I, offset: usize) -> Option<String>
+where
+    I: Input + ?Sized,
+{
+    let next = input.next(offset)?;
+    Some(input.describe(offset..next))
+}
+
+/// The error for expecting something at `offset` in the input.
+/// This is what atoms report when they fail, with the item
+/// they found, or the end of the input if there is nothing there.
+pub(crate) fn unexpected<T, I>(
+    input: &I,
+    expected: impl ToString,
+    offset: usize,
+) -> Result<T, Error>
+where
+    I: Input + ?Sized,
+{
+    match peek(input, offset) {
+        Some(actual) => Error::new(actual, expected, offset),
+        None => Error::end(expected, offset),
+    }
+}
+
+/// The error for a loop whose parser succeeded without consuming
+/// any input, so the loop can never make progress. This is always
+/// a mistake in the grammar, so debug builds panic instead.
+pub(crate) fn stalled<I>(input: &I, expectation: &str, offset: usize) -> Error
+where
+    I: Input + ?Sized,
+{
+    debug_assert!(false, "{} was repeated without consuming input", expectation);
+    unexpected::<(), _>(
+        input,
+        format!("{} to consume input", expectation),
+        offset,
+    )
+    .unwrap_err()
+}
+
+/// The fewest and most times a range allows something to repeat,
+/// or None if the range is empty
+fn repetitions(range: &impl RangeBounds<usize>) -> Option<(usize, usize)> {
+    let lower = match range.start_bound() {
+        Unbounded => 0,
+        Included(n) => *n,
+        Excluded(n) => n.checked_add(1)?,
+    };
+    let upper = match range.end_bound() {
+        Unbounded => usize::MAX,
+        Included(n) => *n,
+        Excluded(n) => n.checked_sub(1)?,
+    };
+    if lower <= upper {
+        Some((lower, upper))
+    } else {
+        None
+    }
+}
+
+/// A value along with the byte range of the input it was parsed from.
+/// This is the output of `Parser::spanned`, and is useful for pointing
+/// back into the source after parsing, like when type checking an AST.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub span: Range<usize>,
+}
+
+/// The result of parsing input that may not have ended yet.
+/// This is the output of `Parser::parse_partial`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Partial<T> {
+    /// The output, and the offset of the input after it
+    Done(T, usize),
+    /// More input is needed to know the result
+    Incomplete,
+    /// Parsing failed, and more input won't change that
+    Failed(Error),
+}
 
 /// A Parser has a function that consumes input
 /// and returns an object of type Output.
-#[derive(Clone)]
-pub struct Parser<T> {
-    parser: Arc<dyn Fn(&str) -> Output<T>>,
+/// Parsers parse text by default, but can parse any Input.
+pub struct Parser<T, I: ?Sized = str> {
+    parser: Arc<ParseFn<T, I>>,
     pub expectation: String,
 }
 
-impl<T> Parser<T>
+/// Parsers are cheap to clone, whatever they parse
+impl<T, I: ?Sized> Clone for Parser<T, I> {
+    fn clone(&self) -> Self {
+        Self {
+            parser: self.parser.clone(),
+            expectation: self.expectation.clone(),
+        }
+    }
+}
+
+impl<T, I> Parser<T, I>
 where
     T: 'static + Clone,
+    I: Input + ?Sized + 'static,
 {
     /// Create a new parser from a function that returns an Output.
     /// This is mainly used to define the atomic combinators
-    pub fn new(parser: impl Fn(&str) -> Output<T> + 'static, expectation: impl ToString) -> Self {
+    pub fn new(
+        parser: impl Fn(&mut Context<I>, usize) -> Output<T> + 'static,
+        expectation: impl ToString,
+    ) -> Self {
         Self {
             parser: Arc::new(parser),
             expectation: expectation.to_string(),
         }
     }
 
-    pub fn expects(mut self, expectation: impl ToString) -> Self {
-        self.expectation = expectation.to_string();
-        self
+    /// Label this parser. If it fails without getting past where
+    /// it started, the error expects this label instead of whatever
+    /// the parser's components expected.
+    pub fn expects(self, expectation: impl ToString) -> Self {
+        let expectation = expectation.to_string();
+        let label = expectation.clone();
+        let relabel = move |mut e: Error, offset: usize| {
+            if e.offset == offset {
+                e.expected = vec![label.clone()];
+            }
+            e
+        };
+        Parser::new(
+            move |ctx: &mut Context<I>, offset: usize| {
+                // Errors recorded by our components are relabelled too
+                let outer = ctx.furthest.take();
+                let result = self.parse_internal(ctx, offset);
+                let inner = ctx.furthest.take();
+                ctx.furthest = outer;
+                if let Some(e) = inner {
+                    ctx.record(relabel(e, offset));
+                }
+                result.map_err(|e| relabel(e, offset))
+            },
+            expectation,
+        )
     }
 
     /// This parses a string using this combinator, and returns
     /// a result containing either the successfully lexed and parsed
     /// data, or an error containing info about the failure.
-    pub fn parse(&self, input: &str) -> Result<T, Error> {
-        match self.parse_internal(input) {
-            Ok(t) => Ok(t.0),
-            Err(e) => Error::new(e.actual, self.expectation.clone(), e.remaining_input),
+    /// If parsing fails, the error is the furthest failure of any
+    /// alternative that was tried, unless the error is fatal.
+    /// A fatal error is reported where it happened.
+    /// If parsing recovered from any errors, the first one is returned.
+    pub fn parse(&self, input: &I) -> Result<T, Error> {
+        self.parse_prefix(input).0.map(|t| t.0)
+    }
+
+    /// This parses input that may not have ended yet, like the chunks of
+    /// a stream read so far. If the result could change once more input
+    /// arrives, because some parser looked at the end of the input,
+    /// parsing is incomplete. Otherwise, this returns the output and the
+    /// offset of the input after it, or the error `parse` would return.
+    pub fn parse_partial(&self, input: &I) -> Partial<T> {
+        match self.parse_prefix(input) {
+            (_, true) => Partial::Incomplete,
+            (Ok((t, end)), false) => Partial::Done(t, end),
+            (Err(e), false) => Partial::Failed(e),
         }
     }
 
+    /// Parse the start of the input like `parse`, but also return the
+    /// offset of the input after the output, and whether any parser
+    /// looked at the end of the input.
+    pub(crate) fn parse_prefix(&self, input: &I) -> (Result<(T, usize), Error>, bool) {
+        let mut ctx = Context::new(input);
+        let result = self.parse_context(&mut ctx);
+        (result, ctx.reached_end)
+    }
+
+    /// Parse the start of the context's input, and return
+    /// the error `parse` should report if parsing fails
+    fn parse_context(&self, ctx: &mut Context<I>) -> Result<(T, usize), Error> {
+        let result = self.parse_internal(ctx, 0);
+        let result = match ctx.recovered.first() {
+            Some(e) => Err(e.clone()),
+            None => result.map_err(|e| ctx.furthest(e)),
+        };
+        result.map_err(|e| e.locate(ctx.input()))
+    }
+
+    /// This parses input like `parse`, but parsers can also use and change
+    /// a state, like a table of the names declared so far, through
+    /// `Parser::with_state`, `atoms::get_state` and `atoms::set_state`.
+    /// When parsing backtracks, changes to the state are undone,
+    /// so the state is cloned at every alternative and should be cheap
+    /// to clone. If parsing succeeds, the state is updated.
+    ///
+    /// Memoized parsers are not run again when their result is remembered,
+    /// so they should not depend on or change the state.
+    pub fn parse_with_state<S>(&self, input: &I, state: &mut S) -> Result<T, Error>
+    where
+        S: Any + Clone,
+    {
+        let mut ctx = Context::new(input);
+        ctx.state = Some(Box::new(state.clone()));
+        let (output, _) = self.parse_context(&mut ctx)?;
+        *state = ctx.state::<S>().clone();
+        Ok(output)
+    }
+
+    /// This parses a string like `parse`, but keeps going after errors
+    /// that parsers like `atoms::recover_until` recover from. This returns
+    /// the output, if parsing succeeded, along with every error in the
+    /// order they happened. This is useful for reporting every problem
+    /// with the input at once.
+    pub fn parse_recovering(&self, input: &I) -> (Option<T>, Vec<Error>) {
+        let mut ctx = Context::new(input);
+        let result = self.parse_internal(&mut ctx, 0);
+        let mut errors = core::mem::take(&mut ctx.recovered);
+        let output = match result {
+            Ok(t) => Some(t.0),
+            Err(e) => {
+                errors.push(ctx.furthest(e));
+                None
+            }
+        };
+        let errors = errors.into_iter().map(|e| e.locate(input)).collect();
+        (output, errors)
+    }
+
     /// This is used by the atomic combinators for things like
     /// control flow and passing the output of one parser into another.
-    pub fn parse_internal(&self, input: &str) -> Output<T> {
-        (self.parser)(input)
+    /// The context holds the entire input given to `parse`, and
+    /// `offset` is the byte offset of the unparsed remainder.
+    pub fn parse_internal(&self, ctx: &mut Context<I>, offset: usize) -> Output<T> {
+        (self.parser)(ctx, offset)
+    }
+
+    /// This method returns a parser that remembers its result at every
+    /// offset it is used at during a call to `parse`. If the parser is
+    /// tried again at the same offset, say by another alternative of an
+    /// `or`, the remembered result is returned without parsing again.
+    /// This makes grammars that backtrack a lot run in linear time.
+    ///
+    /// A clone of the returned parser shares its results. To memoize a
+    /// recursive rule, use `atoms::memo_rec` instead.
+    pub fn memo(self) -> Parser<T, I> {
+        self.memoize(MemoKey::Parser(NEXT_MEMO.fetch_add(1, Ordering::Relaxed)))
+    }
+
+    /// Memoize this parser's results under the given key
+    pub(crate) fn memoize(self, key: MemoKey) -> Parser<T, I> {
+        let expect = self.expectation.clone();
+        Parser::new(
+            move |ctx: &mut Context<I>, offset: usize| ctx.memoized(key, &self, offset),
+            expect,
+        )
+    }
+
+    /// This method returns a parser whose failures are fatal. Once this
+    /// parser is reached, `or`, `opt` and `repeat` won't backtrack and try
+    /// something else if it fails, so the error is reported right here
+    /// instead of wherever an unrelated alternative got to.
+    /// To make every failure after a point fatal, use `atoms::cut`.
+    pub fn commit(self) -> Parser<T, I> {
+        let expect = self.expectation.clone();
+        Parser::new(
+            move |ctx: &mut Context<I>, offset: usize| {
+                self.parse_internal(ctx, offset).map_err(Error::fatal)
+            },
+            expect,
+        )
     }
 
     /// This method takes a function that takes the output of this Parser,
     /// and converts it to the output of another data type.
     /// This allows us to lex our input as we parse it.
-    pub fn map<O>(self, map_fn: fn(T) -> O) -> Parser<O>
+    /// The function can be any closure, so it can capture things
+    /// like an interner or a symbol table.
+    pub fn map<O>(self, map_fn: impl Fn(T) -> O + 'static) -> Parser<O, I>
     where
         O: 'static + Clone,
     {
         let expect = self.expectation.clone();
         Parser::new(
-            move |s: &str| match self.parse_internal(s) {
-                Ok((first_out, input)) => Ok((map_fn(first_out), input)),
+            move |ctx: &mut Context<I>, offset: usize| match self.parse_internal(ctx, offset) {
+                Ok((first_out, remaining)) => Ok((map_fn(first_out), remaining)),
                 Err(e) => Err(e),
             },
             expect,
         )
     }
 
+    /// This method takes a function that builds the next parser from
+    /// the output of this one, and returns a parser that consumes both.
+    /// This lets earlier input decide how later input is parsed, like
+    /// a length prefix deciding how many bytes to take, or a header
+    /// deciding the grammar of the body.
+    pub fn then<O>(self, next_fn: impl Fn(T) -> Parser<O, I> + 'static) -> Parser<O, I>
+    where
+        O: 'static + Clone,
+    {
+        let expect = self.expectation.clone() + " followed by what it decides";
+        Parser::new(
+            move |ctx: &mut Context<I>, offset: usize| {
+                let (first_out, remaining) = self.parse_internal(ctx, offset)?;
+                next_fn(first_out).parse_internal(ctx, remaining)
+            },
+            expect,
+        )
+    }
+
+    /// This method takes a function that takes the state given to
+    /// `parse_with_state` and the output of this Parser, and converts
+    /// the output like `map`. The function can also change the state.
+    /// If parsing backtracks from this parser, the change is undone.
+    pub fn with_state<S, O>(self, state_fn: impl Fn(&mut S, T) -> O + 'static) -> Parser<O, I>
+    where
+        S: Any,
+        O: 'static + Clone,
+    {
+        let expect = self.expectation.clone();
+        Parser::new(
+            move |ctx: &mut Context<I>, offset: usize| {
+                let (first_out, remaining) = self.parse_internal(ctx, offset)?;
+                Ok((state_fn(ctx.state(), first_out), remaining))
+            },
+            expect,
+        )
+    }
+
     /// This method takes a function that takes the output of this Parser,
     /// and TRIES to convert it to the output of another data type.
     /// If the given function returns an Err, this parser fails.
-    pub fn convert<O, E>(self, convert_fn: fn(T) -> Result<O, E>) -> Parser<O>
+    pub fn convert<O, E>(self, convert_fn: impl Fn(T) -> Result<O, E> + 'static) -> Parser<O, I>
     where
         O: 'static + Clone,
         E: 'static,
     {
         let expect = self.expectation.clone();
         Parser::new(
-            move |s: &str| match self.parse_internal(s) {
-                Ok((first_out, input)) => {
-                    let result = match convert_fn(first_out) {
-                        Ok(value) => value,
-                        Err(_) => return Error::new(s.clone(), "A convertible value", s.clone()),
-                    };
-                    Ok((result, input))
+            move |ctx: &mut Context<I>, offset: usize| {
+                let outer = ctx.furthest.take();
+                let result = match self.parse_internal(ctx, offset) {
+                    Ok((first_out, remaining)) => match convert_fn(first_out) {
+                        Ok(value) => Ok((value, remaining)),
+                        Err(_) => {
+                            // The parser matched, so anything it backtracked
+                            // from is less relevant than the failed conversion.
+                            ctx.furthest = None;
+                            let actual = ctx.input().describe(offset..remaining);
+                            Error::new(actual, &self.expectation, offset)
+                        }
+                    },
+                    Err(e) => Err(e),
+                };
+                if let Some(e) = outer {
+                    ctx.record(e);
                 }
-                Err(e) => Err(e),
+                result
+            },
+            expect,
+        )
+    }
+
+    /// This method returns a parser that also returns the byte range
+    /// of the input that this parser consumed.
+    pub fn spanned(self) -> Parser<Spanned<T>, I> {
+        let expect = self.expectation.clone();
+        Parser::new(
+            move |ctx: &mut Context<I>, offset: usize| {
+                let (value, remaining) = self.parse_internal(ctx, offset)?;
+                let span = offset..remaining;
+                Ok((Spanned { value, span }, remaining))
             },
             expect,
         )
@@ -143,18 +1168,18 @@ where
     /// This parser "prefixes" another.
     /// When the returned parser is used, it will require this parser and
     /// the operand parser to succeed, and return the result of the second.
-    pub fn prefixes<O>(self, operand: Parser<O>) -> Parser<O>
+    pub fn prefixes<O>(self, operand: Parser<O, I>) -> Parser<O, I>
     where
         O: 'static + Clone,
     {
         let expect = self.expectation.clone() + " followed by " + &operand.expectation.clone();
         Parser::new(
-            move |s: &str| {
+            move |ctx: &mut Context<I>, offset: usize| {
                 // Get the remaining input from ourselves
                 // and discard consumed input
-                let (_, remaining) = self.parse_internal(s)?;
+                let (_, remaining) = self.parse_internal(ctx, offset)?;
                 // Get the consumed input and remaining input from operand
-                let (consumed, remaining) = operand.parse_internal(&remaining)?;
+                let (consumed, remaining) = operand.parse_internal(ctx, remaining)?;
                 // Return result
                 Ok((consumed, remaining))
             },
@@ -165,18 +1190,18 @@ where
     /// This parser will use the operand as a "suffix".
     /// The parser will only succeed if the "suffix" parser succeeds afterwards,
     /// but the input of the "suffix" parser will be discarded.
-    pub fn suffix<O>(self, operand: Parser<O>) -> Parser<T>
+    pub fn suffix<O>(self, operand: Parser<O, I>) -> Parser<T, I>
     where
         O: 'static + Clone,
     {
         let expect = self.expectation.clone() + " followed by " + &operand.expectation.clone();
         Parser::new(
-            move |s: &str| {
+            move |ctx: &mut Context<I>, offset: usize| {
                 // Get consumed input and remaining input from ourselves
-                let (consumed, remaining) = self.parse_internal(s)?;
+                let (consumed, remaining) = self.parse_internal(ctx, offset)?;
                 // Consume the input from the remaining,
                 // but discard the consumed result.
-                let (_, remaining) = operand.parse_internal(&remaining)?;
+                let (_, remaining) = operand.parse_internal(ctx, remaining)?;
                 // Return result
                 Ok((consumed, remaining))
             },
@@ -187,14 +1212,17 @@ where
     /// This method returns a parser that does not consume input,
     /// but succeeds if this parser succeeds. This can be used to
     /// make assertions for our input.
-    pub fn is(self) -> Parser<()> {
+    pub fn is(self) -> Parser<(), I> {
         let expect = self.expectation.clone();
         Parser::new(
-            move |s: &str| match self.parse_internal(s) {
+            move |ctx: &mut Context<I>, offset: usize| match ctx.lookahead(&self, offset) {
                 // If this parser succeeds, consume nothing and continue
-                Ok(_) => Ok(((), s.to_string())),
+                Ok(_) => Ok(((), offset)),
                 // If this parser fails, throw an error
-                Err(_) => Error::new(s, format!("Not {}", s), s),
+                Err(e) => match e.actual {
+                    Some(actual) => Error::new(actual, &self.expectation, offset),
+                    None => Error::end(&self.expectation, offset),
+                },
             },
             expect,
         )
@@ -203,14 +1231,18 @@ where
     /// This method returns a parser that does not consume input,
     /// but succeeds if this parser does not succeed. This can be
     /// used to make assertions for our input.
-    pub fn isnt(self) -> Parser<()> {
+    pub fn isnt(self) -> Parser<(), I> {
         let expect = self.expectation.clone();
         Parser::new(
-            move |s: &str| match self.parse_internal(s) {
+            move |ctx: &mut Context<I>, offset: usize| match ctx.lookahead(&self, offset) {
                 // If this parser succeeds, throw an error
-                Ok(_) => Error::new(s, format!("Not {}", self.expectation), s),
+                Ok(_) => unexpected(
+                    ctx.input(),
+                    format!("Not {}", self.expectation),
+                    offset,
+                ),
                 // If this parser fails, consume nothing and continue
-                Err(_) => Ok(((), s.to_string())),
+                Err(_) => Ok(((), offset)),
             },
             format!("Not {}", expect),
         )
@@ -220,17 +1252,17 @@ where
     /// inputs into a tuple.
     /// Parser<A> & Parser<B> -> Parser<A, B>.
     /// The resulting parser will only succeed if BOTH sub-parsers succeed.
-    pub fn and<O>(self, operand: Parser<O>) -> Parser<(T, O)>
+    pub fn and<O>(self, operand: Parser<O, I>) -> Parser<(T, O), I>
     where
         O: 'static + Clone,
     {
         let expect = self.expectation.clone() + " and " + &operand.expectation.clone();
         Parser::new(
-            move |s: &str| {
+            move |ctx: &mut Context<I>, offset: usize| {
                 // Get the first consumed and remaining
-                let (first_consumed, remaining) = self.parse_internal(s)?;
+                let (first_consumed, remaining) = self.parse_internal(ctx, offset)?;
                 // Get the second consumed and remaining
-                let (second_consumed, remaining) = operand.parse_internal(&remaining)?;
+                let (second_consumed, remaining) = operand.parse_internal(ctx, remaining)?;
                 // Return a tuple of first and second
                 Ok(((first_consumed, second_consumed), remaining))
             },
@@ -242,12 +1274,24 @@ where
     pub fn or(self, operand: Self) -> Self {
         let expect = self.expectation.clone() + " or " + &operand.expectation.clone();
         Parser::new(
-            move |s: &str| match self.parse_internal(s) {
+            move |ctx: &mut Context<I>, offset: usize| match ctx.alternative(&self, offset) {
                 // If we succeed, return OUR result
                 Ok(t) => Ok(t),
-                // If we don't succeed, return the other parser's result
-                // We can safely discard OUR error here because we expect failure.
-                Err(_) => operand.parse_internal(s),
+                // If we committed to this alternative, don't try the other
+                Err(e) if e.fatal => Err(e),
+                // If we don't succeed, return the other parser's result.
+                // Our error is kept in case it got further than the other's.
+                Err(e) => match ctx.alternative(&operand, offset) {
+                    Ok(t) => {
+                        ctx.record(e);
+                        Ok(t)
+                    }
+                    Err(other) if other.fatal => {
+                        ctx.record(e);
+                        Err(other)
+                    }
+                    Err(other) => Err(e.merge(other)),
+                },
             },
             expect,
         )
@@ -255,43 +1299,72 @@ where
 
     /// Repeat this parser N..M times
     /// This can also be repeated ..N times, N.. times, or even .. times
-    pub fn repeat(self, range: impl RangeBounds<usize>) -> Parser<Vec<T>> {
-        // Get the upper bound
-        let upper_bound: usize = match range.end_bound() {
-            Unbounded => core::usize::MAX,
-            Excluded(n) => *n,
-            Included(n) => *n,
-        };
-
-        // Get the lower bound
-        let lower_bound: usize = match range.start_bound() {
-            Unbounded => 0,
-            Excluded(n) => *n,
-            Included(n) => *n,
+    /// Bounds work like they do for slices, so `0..3` means at most
+    /// twice and `0..=3` means at most three times. An empty range,
+    /// like `3..3` or `5..2`, makes a parser that always fails.
+    /// The repetition stops early if this parser consumes nothing,
+    /// and fails if that happens before the lower bound is reached.
+    pub fn repeat(self, range: impl RangeBounds<usize>) -> Parser<Vec<T>, I> {
+        let (lower_bound, upper_bound) = match repetitions(&range) {
+            Some(bounds) => bounds,
+            None => {
+                let expect = format!(
+                    "{} repeated a number of times in {:?}, which is an empty range",
+                    self.expectation,
+                    (range.start_bound(), range.end_bound())
+                );
+                let expectation = expect.clone();
+                return Parser::new(
+                    move |ctx: &mut Context<I>, offset: usize| {
+                        unexpected(ctx.input(), &expectation, offset)
+                    },
+                    expect,
+                );
+            }
         };
 
-        let expect = self.expectation.clone()
-            + &format!(" {:?}..{:?} times", range.start_bound(), range.end_bound());
+        let expect = format!(
+            "{} {}",
+            self.expectation,
+            match (lower_bound, upper_bound) {
+                (0, usize::MAX) => String::from("any number of times"),
+                (n, usize::MAX) => format!("at least {} times", n),
+                (0, m) => format!("at most {} times", m),
+                (n, m) if n == m => format!("exactly {} times", n),
+                (n, m) => format!("{} to {} times", n, m),
+            }
+        );
         Parser::new(
-            move |s: &str| {
-                // The string containing the remaining input
-                let mut remaining_input = s.to_string();
+            move |ctx: &mut Context<I>, offset: usize| {
+                // The offset of the remaining input
+                let mut remaining_input = offset;
                 // This accumulates all the consumed and lexed outputs
                 // from all the successfully parsed inputs
                 let mut accum = vec![];
 
                 for n in 0..upper_bound {
-                    match self.parse_internal(&remaining_input) {
+                    match ctx.alternative(&self, remaining_input) {
+                        // Repeating a parser that consumed nothing would
+                        // give the same output forever, so stop here
+                        Ok((_, unconsumed)) if unconsumed == remaining_input => {
+                            if n < lower_bound {
+                                return Err(stalled(ctx.input(), &self.expectation, unconsumed));
+                            }
+                            return Ok((accum, remaining_input));
+                        }
                         Ok((consumed, unconsumed)) => {
                             accum.push(consumed);
                             remaining_input = unconsumed;
                         }
+                        // If we committed to another repetition, it must succeed
+                        Err(e) if e.fatal => return Err(e),
                         Err(e) => {
                             // If we did not consume enough data, we failed.
                             // If consumed greater than the lower bound, we succeeded!
                             if n < lower_bound {
                                 return Err(e);
                             } else {
+                                ctx.record(e);
                                 return Ok((accum, remaining_input));
                             }
                         }
@@ -303,10 +1376,25 @@ where
             expect,
         )
     }
+
+    /// Repeat this parser exactly N times
+    pub fn exactly(self, n: usize) -> Parser<Vec<T>, I> {
+        self.repeat(n..=n)
+    }
+
+    /// Repeat this parser N or more times
+    pub fn at_least(self, n: usize) -> Parser<Vec<T>, I> {
+        self.repeat(n..)
+    }
+
+    /// Repeat this parser at most N times
+    pub fn at_most(self, n: usize) -> Parser<Vec<T>, I> {
+        self.repeat(..=n)
+    }
 }
 
 /// The | operator can be used as an alternative to the `.or` method
-impl<T: 'static + Clone, S: ToString> Rem<S> for Parser<T> {
+impl<T: 'static + Clone, I: Input + ?Sized + 'static, S: ToString> Rem<S> for Parser<T, I> {
     type Output = Self;
     fn rem(self, rhs: S) -> Self::Output {
         self.expects(rhs)
@@ -314,75 +1402,99 @@ impl<T: 'static + Clone, S: ToString> Rem<S> for Parser<T> {
 }
 
 /// The | operator can be used as an alternative to the `.or` method
-impl<T: 'static + Clone> BitOr for Parser<T> {
-    type Output = Parser<T>;
+impl<T: 'static + Clone, I: Input + ?Sized + 'static> BitOr for Parser<T, I> {
+    type Output = Parser<T, I>;
     fn bitor(self, rhs: Self) -> Self::Output {
         self.or(rhs)
     }
 }
 
 /// The & operator can be used as an alternative to the `.and` method
-impl<A: 'static + Clone, B: 'static + Clone> BitAnd<Parser<B>> for Parser<A> {
-    type Output = Parser<(A, B)>;
-    fn bitand(self, rhs: Parser<B>) -> Self::Output {
+impl<A, B, I> BitAnd<Parser<B, I>> for Parser<A, I>
+where
+    A: 'static + Clone,
+    B: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    type Output = Parser<(A, B), I>;
+    fn bitand(self, rhs: Parser<B, I>) -> Self::Output {
         self.and(rhs)
     }
 }
 
 /// The ! operator can be used as an alternative to the `.not` method
-impl<T: 'static + Clone> Not for Parser<T> {
-    type Output = Parser<()>;
+impl<T: 'static + Clone, I: Input + ?Sized + 'static> Not for Parser<T, I> {
+    type Output = Parser<(), I>;
     fn not(self) -> Self::Output {
         self.isnt()
     }
 }
 
 /// Discard the consumed data of the RHS
-impl<A: 'static + Clone, B: 'static + Clone> Shl<Parser<B>> for Parser<A> {
-    type Output = Parser<A>;
-    fn shl(self, rhs: Parser<B>) -> Self::Output {
+impl<A, B, I> Shl<Parser<B, I>> for Parser<A, I>
+where
+    A: 'static + Clone,
+    B: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    type Output = Parser<A, I>;
+    fn shl(self, rhs: Parser<B, I>) -> Self::Output {
         self.suffix(rhs)
     }
 }
 
 /// Discard the consumed data of the LHS
-impl<A: 'static + Clone, B: 'static + Clone> Shr<Parser<B>> for Parser<A> {
-    type Output = Parser<B>;
-    fn shr(self, rhs: Parser<B>) -> Self::Output {
+impl<A, B, I> Shr<Parser<B, I>> for Parser<A, I>
+where
+    A: 'static + Clone,
+    B: 'static + Clone,
+    I: Input + ?Sized + 'static,
+{
+    type Output = Parser<B, I>;
+    fn shr(self, rhs: Parser<B, I>) -> Self::Output {
         self.prefixes(rhs)
     }
 }
 
 /// A parser can be multiplied by a range as an alternative to `.repeat`.
 /// Here's an example: `sym('a') * (..7)`
-impl<T: 'static + Clone, R: RangeBounds<usize>> Mul<R> for Parser<T> {
-    type Output = Parser<Vec<T>>;
+impl<T, I, R> Mul<R> for Parser<T, I>
+where
+    T: 'static + Clone,
+    I: Input + ?Sized + 'static,
+    R: RangeBounds<usize>,
+{
+    type Output = Parser<Vec<T>, I>;
     fn mul(self, rhs: R) -> Self::Output {
         self.repeat(rhs)
     }
 }
 
 /// The - operator is used as an alternative to the `.map` method.
-impl<O, T> Sub<fn(T) -> O> for Parser<T>
+impl<O, T, I, F> Sub<F> for Parser<T, I>
 where
     O: 'static + Clone,
     T: 'static + Clone,
+    I: Input + ?Sized + 'static,
+    F: Fn(T) -> O + 'static,
 {
-    type Output = Parser<O>;
-    fn sub(self, rhs: fn(T) -> O) -> Self::Output {
+    type Output = Parser<O, I>;
+    fn sub(self, rhs: F) -> Self::Output {
         self.map(rhs)
     }
 }
 
 /// The ^ operator is used as an alternative to the `.convert` method.
-impl<O, T, E> BitXor<fn(T) -> Result<O, E>> for Parser<T>
+impl<O, T, I, E, F> BitXor<F> for Parser<T, I>
 where
     O: 'static + Clone,
     T: 'static + Clone,
+    I: Input + ?Sized + 'static,
     E: 'static,
+    F: Fn(T) -> Result<O, E> + 'static,
 {
-    type Output = Parser<O>;
-    fn bitxor(self, rhs: fn(T) -> Result<O, E>) -> Self::Output {
+    type Output = Parser<O, I>;
+    fn bitxor(self, rhs: F) -> Self::Output {
         self.convert(rhs)
     }
 }
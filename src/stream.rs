@@ -0,0 +1,165 @@
+// This module contains the Stream driver, which parses items
+// from input that arrives a chunk at a time.
+
+use crate::{unexpected, Error, Parser};
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+
+/// The chunks of a stream, or the reason a chunk couldn't be read
+type Chunks<'a> = Box<dyn Iterator<Item = Result<String, String>> + 'a>;
+
+/// A Stream parses items from input that arrives a chunk at a time,
+/// like a `repeat` of the item parser that yields each item as soon as
+/// it is complete. Only the input for the items that haven't been parsed
+/// yet is kept in memory, so a stream can parse more input than fits.
+///
+/// Items are parsed with `Parser::parse_partial`, so an item is only
+/// yielded once more input can't change it. After the last chunk,
+/// the rest of the input must be made of whole items.
+/// The stream stops after yielding an error.
+pub struct Stream<'a, T> {
+    parser: Parser<T>,
+    chunks: Chunks<'a>,
+    /// The input that hasn't been parsed yet
+    buffer: String,
+    /// Whether every chunk has been read
+    ended: bool,
+    /// Whether an error has been yielded
+    failed: bool,
+    /// The offset, line, and column in the stream that the buffer starts at
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a, T> Stream<'a, T>
+where
+    T: 'static + Clone,
+{
+    fn new(parser: Parser<T>, chunks: Chunks<'a>) -> Self {
+        Self {
+            parser,
+            chunks,
+            buffer: String::new(),
+            ended: false,
+            failed: false,
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    /// Read the next chunk into the buffer, or note the stream has ended
+    fn read(&mut self) -> Result<(), Error> {
+        match self.chunks.next() {
+            Some(Ok(chunk)) => self.buffer.push_str(&chunk),
+            Some(Err(reason)) => {
+                let error = Error::new::<()>(reason, "more input", self.buffer.len()).unwrap_err();
+                return Err(self.locate(error));
+            }
+            None => self.ended = true,
+        }
+        Ok(())
+    }
+
+    /// Drop the input an item was parsed from
+    fn consume(&mut self, end: usize) {
+        let consumed = &self.buffer[..end];
+        match consumed.rfind('\n') {
+            Some(n) => {
+                self.line += consumed.matches('\n').count();
+                self.column = consumed[n + 1..].chars().count() + 1;
+            }
+            None => self.column += consumed.chars().count(),
+        }
+        self.offset += end;
+        self.buffer.drain(..end);
+    }
+
+    /// Locate an error in the buffer, and then in the whole stream
+    fn locate(&self, error: Error) -> Error {
+        error
+            .locate(self.buffer.as_str())
+            .shift(self.offset, self.line, self.column)
+    }
+}
+
+impl<T> Iterator for Stream<'_, T>
+where
+    T: 'static + Clone,
+{
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        loop {
+            if self.buffer.is_empty() && self.ended {
+                return None;
+            }
+            let (result, reached_end) = self.parser.parse_prefix(self.buffer.as_str());
+            // The item might not be complete until we read more
+            if (reached_end || self.buffer.is_empty()) && !self.ended {
+                if let Err(e) = self.read() {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+                continue;
+            }
+
+            return Some(match result {
+                // An item that consumes nothing would be yielded forever
+                Ok((_, 0)) => {
+                    self.failed = true;
+                    let expected = &self.parser.expectation;
+                    let error = unexpected::<(), _>(self.buffer.as_str(), expected, 0).unwrap_err();
+                    Err(self.locate(error))
+                }
+                Ok((t, end)) => {
+                    self.consume(end);
+                    Ok(t)
+                }
+                Err(e) => {
+                    self.failed = true;
+                    Err(e.shift(self.offset, self.line, self.column))
+                }
+            });
+        }
+    }
+}
+
+impl<T> Parser<T>
+where
+    T: 'static + Clone,
+{
+    /// This returns a Stream that parses items with this parser
+    /// from chunks of input, like the lines of a large file.
+    pub fn stream<'a, C>(&self, chunks: C) -> Stream<'a, T>
+    where
+        C: IntoIterator,
+        C::Item: AsRef<str>,
+        C::IntoIter: 'a,
+    {
+        let chunks = chunks.into_iter().map(|chunk| Ok(chunk.as_ref().to_string()));
+        Stream::new(self.clone(), Box::new(chunks))
+    }
+
+    /// This returns a Stream that parses items with this parser
+    /// from a reader, a line at a time. If reading fails,
+    /// the stream yields an error saying why.
+    #[cfg(feature = "std")]
+    pub fn stream_reader<'a>(&self, mut reader: impl std::io::BufRead + 'a) -> Stream<'a, T> {
+        let chunks = core::iter::from_fn(move || {
+            let mut line = String::new();
+            match reader.read_line(&mut line) {
+                Ok(0) => None,
+                Ok(_) => Some(Ok(line)),
+                Err(e) => Some(Err(e.to_string())),
+            }
+        });
+        Stream::new(self.clone(), Box::new(chunks))
+    }
+}
@@ -1,9 +1,12 @@
 extern crate honeycomb;
 use honeycomb::{
-    basic::{email, phone_number, PhoneNumber},
+    atoms::tok_if,
+    basic::{bencode, email, netstring, phone_number, Bencode, PhoneNumber},
     Error,
 };
 
+use std::collections::BTreeMap;
+
 #[test]
 fn email_test() {
     assert_eq!(
@@ -21,27 +24,21 @@ fn email_test() {
         Ok((String::from("snowball.sweet"), String::from("gmail.com")))
     );
 
+    let error = email().parse("snowball. sweet@gmail.com").unwrap_err();
+    assert_eq!(error.offset(), 9);
     assert_eq!(
-        email().parse("snowball. sweet@gmail.com"),
-        Error::new(" ", "a valid email address", " sweet@gmail.com")
+        error.expected(),
+        ["-", ".", "@", "_", "an alphanumeric character"]
     );
 
     assert_eq!(
         email().parse("–autumn-dancer@domain.com"),
-        Error::new(
-            "–autumn-dancer@domain.com",
-            "a valid email address",
-            "–autumn-dancer@domain.com"
-        )
+        Error::new("–", "a valid email address", 0)
     );
 
     assert_eq!(
         email().parse(".snowball.sweet@domain.com"),
-        Error::new(
-            ".snowball.sweet@domain.com",
-            "a valid email address",
-            ".snowball.sweet@domain.com"
-        )
+        Error::new(".", "a valid email address", 0)
     );
 }
 
@@ -80,3 +77,68 @@ fn phone_number_test() {
 
     assert_eq!(phone_number().parse("123 4567890"), Ok(test_number.clone()));
 }
+
+#[test]
+fn bencode_test() {
+    let bencode = bencode() << !tok_if(|_: &u8| true);
+    assert_eq!(bencode.parse(b"i42e"), Ok(Bencode::Int(42)));
+    assert_eq!(bencode.parse(b"i-7e"), Ok(Bencode::Int(-7)));
+    assert_eq!(bencode.parse(b"i0e"), Ok(Bencode::Int(0)));
+    assert_eq!(bencode.parse(b"4:spam"), Ok(Bencode::Bytes(b"spam".to_vec())));
+    assert_eq!(bencode.parse(b"0:"), Ok(Bencode::Bytes(vec![])));
+
+    // Byte strings don't have to be text
+    assert_eq!(
+        bencode.parse(b"3:\xff\x00e"),
+        Ok(Bencode::Bytes(vec![0xff, 0x00, b'e']))
+    );
+
+    let mut info = BTreeMap::new();
+    info.insert(b"length".to_vec(), Bencode::Int(1024));
+    info.insert(b"name".to_vec(), Bencode::Bytes(b"a:b e".to_vec()));
+    let mut torrent = BTreeMap::new();
+    torrent.insert(b"info".to_vec(), Bencode::Dict(info));
+    torrent.insert(
+        b"tags".to_vec(),
+        Bencode::List(vec![
+            Bencode::Bytes(b"x".to_vec()),
+            Bencode::Int(2),
+            Bencode::List(vec![]),
+        ]),
+    );
+    assert_eq!(
+        bencode.parse(b"d4:infod6:lengthi1024e4:name5:a:b ee4:tagsl1:xi2eleee"),
+        Ok(Bencode::Dict(torrent))
+    );
+
+    assert!(bencode.parse(b"i-0e").is_err());
+    assert!(bencode.parse(b"i03e").is_err());
+    assert!(bencode.parse(b"ie").is_err());
+    assert!(bencode.parse(b"5:spam").is_err());
+    assert!(bencode.parse(b"li1e").is_err());
+
+    // Dictionary keys must be sorted and unique
+    assert!(bencode.parse(b"d1:ai1e1:bi2ee").is_ok());
+    let error = bencode.parse(b"d1:bi2e1:ai1ee").unwrap_err();
+    assert_eq!(error.offset(), 7);
+    assert_eq!(error.actual(), "a");
+    assert!(error
+        .expected()
+        .contains(&String::from("a key sorted after the last one")));
+    assert_eq!(bencode.parse(b"d1:ai1e1:ai2ee").unwrap_err().offset(), 7);
+}
+
+#[test]
+fn netstring_test() {
+    assert_eq!(netstring().parse(b"5:hello,"), Ok(b"hello".to_vec()));
+    assert_eq!(netstring().parse(b"0:,"), Ok(vec![]));
+    assert_eq!(netstring().parse(b"3:a,b,"), Ok(b"a,b".to_vec()));
+    assert_eq!(netstring().parse(b"4:hello,").unwrap_err().offset(), 6);
+    assert!(netstring().parse(b"05:hello,").is_err());
+
+    // Lengths count bytes, not characters
+    assert_eq!(
+        netstring().parse("2:\u{e9},".as_bytes()),
+        Ok("\u{e9}".as_bytes().to_vec())
+    );
+}
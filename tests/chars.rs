@@ -1,6 +1,10 @@
 extern crate honeycomb;
 use honeycomb::{
-    atoms::{any, eof, if_take, none_of, seq, sym},
+    atoms::{
+        any, eof, if_take, none_of, none_of_chars, one_of, one_of_chars, position, seq, sym,
+    },
+    language::string,
+    transform::collect,
     Error,
 };
 
@@ -10,14 +14,14 @@ fn is_test() {
 
     assert_eq!(
         sym('t').isnt().parse("test"),
-        Error::new("test", "Not t", "test")
+        Error::new("t", "Not t", 0)
     );
 
     assert_eq!(sym('t').isnt().parse("hey"), Ok(()));
 
     assert_eq!(
         sym('t').is().parse("hey"),
-        Error::new("hey", "t", "hey")
+        Error::new("h", "t", 0)
     );
 }
 
@@ -31,13 +35,13 @@ fn if_take_test() {
     // Test EOF
     assert_eq!(
         (if_take(|ch| ch == 'a') * (1..)).parse(""),
-        Error::new('\0', "result of if_take input Included(1)..Unbounded times".to_string(), "".to_string())
+        Error::end("result of if_take input", 0)
     );
 }
 
 #[test]
 fn eof_test() {
-    assert_eq!(eof().parse("wow bro"), Error::new("w", "EOF", "wow bro"));
+    assert_eq!(eof().parse("wow bro"), Error::new("w", "EOF", 0));
     assert_eq!(eof().parse(""), Ok(()));
 }
 
@@ -46,7 +50,10 @@ fn none_of_test() {
     assert_eq!(none_of(b"test").parse("wow bro"), Ok('w'));
     assert_eq!(
         none_of(b"test").parse(""),
-        Error::new('\0', format!("none of {:?}", "test".chars().collect::<Vec<char>>()), "")
+        Error::end(
+            format!("none of {:?}", "test".chars().collect::<Vec<char>>()),
+            0
+        )
     );
 }
 
@@ -54,7 +61,7 @@ fn none_of_test() {
 fn sym_test() {
     assert_eq!(sym('b').parse("btest"), Ok('b'));
 
-    assert_eq!(sym('t').parse(""), Error::new("\0", "t", ""));
+    assert_eq!(sym('t').parse(""), Error::end("t", 0));
 }
 
 #[test]
@@ -71,3 +78,78 @@ fn any_test() {
         String::from("asdfaksdjhfaksjd{}{}(*&!*&@%&h 12309\n \r\t")
     );
 }
+
+#[test]
+fn position_test() {
+    assert_eq!(position().parse(""), Ok(0));
+    assert_eq!((seq("tes") >> position()).parse("testing"), Ok(3));
+    assert_eq!(
+        ((sym('a') * (..)) >> position() << seq("bc")).parse("aaabc"),
+        Ok(3)
+    );
+}
+
+#[test]
+fn multibyte_test() {
+    assert_eq!(any().parse("é"), Ok('é'));
+    assert_eq!((any() >> position()).parse("é"), Ok(2));
+    assert_eq!(((any() * (..)) - collect).parse("héllo"), Ok(String::from("héllo")));
+    assert_eq!((sym('é') >> sym('t') >> sym('é')).parse("été"), Ok('é'));
+    assert_eq!(
+        (if_take(|ch| ch.is_alphabetic()) * (..)).parse("ñandú!"),
+        Ok(vec!['ñ', 'a', 'n', 'd', 'ú'])
+    );
+    assert_eq!(seq("café").parse("café au lait"), Ok(String::from("café")));
+
+    // A failed sequence reports whole characters, never half of one
+    assert_eq!(seq("abc").parse("aé"), Error::new("aé", "abc", 0));
+}
+
+#[test]
+fn cjk_test() {
+    assert_eq!(((any() * (..)) - collect).parse("日本語"), Ok(String::from("日本語")));
+    assert_eq!((sym('日') >> sym('本') >> position()).parse("日本語"), Ok(6));
+    assert_eq!(string().parse("\"你好，世界\""), Ok(String::from("你好，世界")));
+    assert_eq!(
+        (none_of_chars(&['。']) * (..)).parse("漢字。"),
+        Ok(vec!['漢', '字'])
+    );
+}
+
+#[test]
+fn emoji_test() {
+    // Emoji are four bytes long in UTF-8
+    assert_eq!((any() >> position()).parse("🦀"), Ok(4));
+    assert_eq!(
+        (one_of_chars(&['🦀', '🐝']) * (..)).parse("🐝🦀🐝🍯"),
+        Ok(vec!['🐝', '🦀', '🐝'])
+    );
+
+    let error = (sym('🦀') >> sym('🦀')).parse("🦀🐝").unwrap_err();
+    assert_eq!(error.offset(), 4);
+    assert_eq!(error.column(), 2);
+    assert_eq!(error.actual(), "🐝");
+}
+
+#[test]
+fn combining_test() {
+    // "é" written as "e" followed by a combining acute accent
+    let decomposed = "e\u{301}";
+    assert_eq!((any() * (..)).parse(decomposed), Ok(vec!['e', '\u{301}']));
+    assert_eq!((sym('e') >> sym('\u{301}') >> position()).parse(decomposed), Ok(3));
+    assert_eq!(
+        (none_of(b"x") * (..)).parse(decomposed),
+        Ok(vec!['e', '\u{301}'])
+    );
+}
+
+#[test]
+fn byte_options_test() {
+    // 'ā' is U+0101, which must not match the byte 0x01
+    assert!(one_of(b"\x01").parse("ā").is_err());
+    assert_eq!(none_of(b"\x01").parse("ā"), Ok('ā'));
+
+    // Bytes stand for the character with the same code point
+    assert_eq!(one_of(b"\xe9").parse("é"), Ok('é'));
+    assert!(one_of(b"\xe9").parse("ũ").is_err());
+}
@@ -0,0 +1,100 @@
+extern crate honeycomb;
+use honeycomb::{
+    atoms::{cut, is, opt, rec, seq, seq_no_ws},
+    language::identifier,
+    Error, Parser,
+};
+
+fn statement() -> Parser<String> {
+    (seq("if") >> cut() >> seq_no_ws("(") >> identifier() << seq_no_ws(")")) | identifier()
+}
+
+#[test]
+fn or_test() {
+    assert_eq!(statement().parse("if (x)"), Ok(String::from("x")));
+    assert_eq!(
+        statement().parse("ifx"),
+        Err(Error::new::<()>("x", "(", 2).unwrap_err().fatal())
+    );
+    assert_eq!(statement().parse("x"), Ok(String::from("x")));
+
+    // Without the cut, `if` is an identifier too
+    let backtracking =
+        (seq("if") >> seq_no_ws("(") >> identifier() << seq_no_ws(")")) | identifier();
+    assert_eq!(backtracking.parse("ifx"), Ok(String::from("ifx")));
+}
+
+#[test]
+fn commit_test() {
+    let block = (seq("{") >> (identifier() << seq("}")).commit()) | seq("{}");
+    assert_eq!(block.parse("{a}"), Ok(String::from("a")));
+
+    let error = block.parse("{}").unwrap_err();
+    assert!(error.is_fatal());
+    assert_eq!(error.offset(), 1);
+    assert_eq!(error.actual(), "}");
+}
+
+#[test]
+fn opt_test() {
+    let brackets = opt(seq("[") >> cut() >> seq("]"));
+    assert_eq!(brackets.parse("[]"), Ok(Some(String::from("]"))));
+    assert_eq!(brackets.parse(""), Ok(None));
+    assert_eq!(
+        brackets.parse("[x"),
+        Err(Error::new::<()>("x", "]", 1).unwrap_err().fatal())
+    );
+}
+
+#[test]
+fn repeat_test() {
+    let parens = (seq("(") >> cut() >> seq(")")).repeat(..);
+    assert_eq!(parens.parse("()()").map(|v| v.len()), Ok(2));
+    assert_eq!(
+        parens.parse("()()(").map(|v| v.len()),
+        Err(Error::end::<()>(")", 5).unwrap_err().fatal())
+    );
+
+    // Without the cut, the repetition stops before the unclosed paren
+    let parens = (seq("(") >> seq(")")).repeat(..);
+    assert_eq!(parens.parse("()()(").map(|v| v.len()), Ok(2));
+}
+
+#[test]
+fn scope_test() {
+    // A cut only commits to the innermost alternative it is in,
+    // so `ab` can still be backtracked from once it has been parsed
+    let inner = (seq("a") >> cut() >> seq("b")) | seq("c");
+    let outer = (inner >> seq("d")) | seq("abe");
+    assert_eq!(outer.parse("abe"), Ok(String::from("abe")));
+
+    // Cuts inside a lookahead are ignored
+    let lookahead = (is(seq("a") >> cut() >> seq("b")) >> seq("ab")) | seq("ac");
+    assert_eq!(lookahead.parse("ac"), Ok(String::from("ac")));
+}
+
+#[test]
+fn memo_test() {
+    // A memoized parser commits when its result is reused, too
+    let a = (seq("a") >> cut()).memo();
+    let p = is(a.clone()) >> ((a >> seq("b")) | seq("ac"));
+    assert_eq!(
+        p.parse("ac"),
+        Err(Error::new::<()>("c", "b", 1).unwrap_err().fatal())
+    );
+}
+
+fn value() -> Parser<String> {
+    (seq_no_ws("{") >> cut() >> rec(value) << seq_no_ws("}")) | identifier()
+}
+
+#[test]
+fn local_error_test() {
+    assert_eq!(value().parse("{ { a } }"), Ok(String::from("a")));
+
+    // The error is reported where the object went wrong
+    let error = value().parse("{ { a ] }").unwrap_err();
+    assert!(error.is_fatal());
+    assert_eq!(error.offset(), 6);
+    assert_eq!(error.expected(), ["}"]);
+}
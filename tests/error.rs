@@ -0,0 +1,174 @@
+extern crate honeycomb;
+use honeycomb::{
+    atoms::{opt, seq, seq_no_ws, space, sym},
+    language::{identifier, number},
+    Error, Parser,
+};
+
+#[test]
+fn position_test() {
+    let pair = (identifier() << seq_no_ws("=")) & number();
+
+    let error = pair.parse("name = \n  = 5").unwrap_err();
+    assert_eq!(error.offset(), 10);
+    assert_eq!(error.line(), 2);
+    assert_eq!(error.column(), 3);
+    assert_eq!(error.actual(), "=");
+
+    let error = sym('a').parse("").unwrap_err();
+    assert_eq!((error.offset(), error.line(), error.column()), (0, 1, 1));
+}
+
+#[test]
+fn column_counts_chars_test() {
+    // "é" is two bytes, but one column
+    let error = (seq_no_ws("é") >> sym('b')).parse("é c").unwrap_err();
+    assert_eq!(error.offset(), 3);
+    assert_eq!(error.line(), 1);
+    assert_eq!(error.column(), 3);
+}
+
+#[test]
+fn display_test() {
+    let error = sym('a').parse("\nb").unwrap_err();
+    assert_eq!(
+        format!("{}", error),
+        "Expected `a` but found `\\n` at 1:1"
+    );
+    assert_eq!(format!("{:?}", error), format!("{}", error));
+
+    assert_eq!(
+        (sym('\n') >> sym('a')).parse("\nb"),
+        Error::new("b", "a", 1)
+    );
+    assert_eq!(
+        format!("{}", (sym('\n') >> sym('a')).parse("\nb").unwrap_err()),
+        "Expected `a` but found `b` at 2:1"
+    );
+}
+
+#[test]
+fn furthest_failure_test() {
+    // The first alternative gets further, so its error is reported
+    let error = ((sym('a') >> sym('b')) | sym('c')).parse("ax").unwrap_err();
+    assert_eq!(error.offset(), 1);
+    assert_eq!(error.expected(), ["b"]);
+
+    // Alternatives failing at the same position are merged
+    let error = (sym('a') | sym('b') | sym('c')).parse("x").unwrap_err();
+    assert_eq!(error.expected(), ["a", "b", "c"]);
+    assert_eq!(
+        format!("{}", error),
+        "Expected one of `a`, `b`, `c` but found `x` at 1:1"
+    );
+
+    // A repetition that stopped early is merged with what failed after it
+    let error = ((sym('a') * (..)) >> sym('b')).parse("aaac").unwrap_err();
+    assert_eq!(error.offset(), 3);
+    assert_eq!(error.expected(), ["a", "b"]);
+
+    // So is an optional parser that didn't match
+    let error = (opt(sym('-')) >> sym('1')).parse("2").unwrap_err();
+    assert_eq!(error.expected(), ["-", "1"]);
+}
+
+#[test]
+fn label_test() {
+    // A label replaces the expectations of a parser that made no progress
+    let error = (identifier() | (number() % "a number")).parse("{").unwrap_err();
+    assert_eq!(error.expected(), ["a number", "an identifier"]);
+
+    // But not of one that failed part way through
+    let error = ((sym('a') >> sym('b')) % "ab").parse("ac").unwrap_err();
+    assert_eq!(error.expected(), ["b"]);
+
+    // Failures inside a lookahead are not reported later on
+    let error = (sym('a').isnt() >> sym('b')).parse("c").unwrap_err();
+    assert_eq!(error.expected(), ["b"]);
+}
+
+#[test]
+fn render_test() {
+    let source = "row {\n    width { 5 }\n    height 10 }\n}";
+    let error = markup().parse(source).unwrap_err();
+    assert_eq!(
+        error.render(source),
+        "error: Expected `{` but found `1`
+ --> 3:12
+  |
+3 |     height 10 }
+  |            ^"
+    );
+
+    // Labels are shown next to the caret, which underlines all of `actual`
+    let error = Error::new::<()>("nul}", "null", 5)
+        .unwrap_err()
+        .with_label("not a JSON value");
+    assert_eq!(
+        error.render("[1,\n\tnul}]"),
+        "error: Expected `null` but found `nul}`
+ --> 2:2
+  |
+2 | \tnul}]
+  | \t^^^^ not a JSON value"
+    );
+
+    // Running out of input points just past the end of the last line
+    let source = "row {\n\n\n\n\n\n\n\n\n  a { 1 }";
+    let error = markup().parse(source).unwrap_err();
+    assert_eq!(
+        error.render(source),
+        "error: Expected one of `an identifier`, `}` but found the end of the input
+  --> 10:10
+   |
+10 |   a { 1 }
+   |          ^"
+    );
+}
+
+#[test]
+fn render_crlf_test() {
+    // The `\r` of a CRLF line ending isn't shown, so an error just
+    // after it points past the end of the line
+    let source = "ab\r\nx";
+    let error = (seq("ab\r") >> sym('x')).parse(source).unwrap_err();
+    assert_eq!(
+        error.render(source),
+        "error: Expected `x` but found `\\n`
+ --> 1:4
+  |
+1 | ab
+  |   ^"
+    );
+}
+
+#[test]
+fn end_of_input_test() {
+    // A NUL character in the input isn't the end of the input
+    let source = "a\0";
+    let error = (sym('a') >> sym('b')).parse(source).unwrap_err();
+    assert!(!error.at_end());
+    assert_eq!(error.actual(), "\0");
+    assert_eq!(
+        error.render(source),
+        "error: Expected `b` but found `\\0`
+ --> 1:2
+  |
+1 | a\0
+  |  ^"
+    );
+
+    // Sequences report running out of input like single characters
+    let error = seq("abc").parse("").unwrap_err();
+    assert!(error.at_end());
+    assert_eq!(error, Error::end::<()>("abc", 0).unwrap_err());
+    assert_eq!(
+        format!("{}", error),
+        "Expected `abc` but found the end of the input at 1:1"
+    );
+}
+
+fn markup() -> Parser<Vec<String>> {
+    let field = space() >> identifier() << seq_no_ws("{") << number() << seq_no_ws("}");
+    identifier() >> seq_no_ws("{") >> (field * (..)) << seq_no_ws("}")
+}
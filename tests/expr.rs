@@ -0,0 +1,129 @@
+extern crate honeycomb;
+use honeycomb::{
+    atoms::{eof, rec, seq_no_ws},
+    expr::{Assoc, Pratt},
+    language::{identifier, number},
+    transform::to_number,
+    Parser,
+};
+
+fn math() -> Parser<f64> {
+    let atom = (number() - to_number) | (seq_no_ws("(") >> rec(math) << seq_no_ws(")"));
+    Pratt::new(atom)
+        .infix(1, Assoc::Left, seq_no_ws("+"), |a, _, b| a + b)
+        .infix(1, Assoc::Left, seq_no_ws("-"), |a, _, b| a - b)
+        .infix(2, Assoc::Left, seq_no_ws("*"), |a, _, b| a * b)
+        .infix(2, Assoc::Left, seq_no_ws("/"), |a, _, b| a / b)
+        .infix(3, Assoc::Right, seq_no_ws("^"), |a, _, b| a.powf(b))
+        .postfix(4, seq_no_ws("!"), |a, _| (1..=a as u64).product::<u64>() as f64)
+        .parser()
+}
+
+#[test]
+fn infix_test() {
+    assert_eq!(math().parse("1 + 2"), Ok(3.0));
+    assert_eq!(math().parse("1 - 2 - 3"), Ok(-4.0));
+    assert_eq!(math().parse("8 / 4 / 2"), Ok(1.0));
+    assert_eq!(math().parse("1 + 2 * 3"), Ok(7.0));
+    assert_eq!(math().parse("2 * 3 + 1"), Ok(7.0));
+    assert_eq!(math().parse("(1 + 2) * 3"), Ok(9.0));
+    assert_eq!(math().parse("2 ^ 3 ^ 2"), Ok(512.0));
+    assert_eq!(math().parse("2 * 3 ^ 2"), Ok(18.0));
+}
+
+#[test]
+fn postfix_test() {
+    assert_eq!(math().parse("3!"), Ok(6.0));
+    assert_eq!(math().parse("3!!"), Ok(720.0));
+    assert_eq!(math().parse("2 * 3!"), Ok(12.0));
+    assert_eq!(math().parse("(1 + 2)! - 1"), Ok(5.0));
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Var(String),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(Box<Expr>, String, Box<Expr>),
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+fn var(name: &str) -> Box<Expr> {
+    Box::new(Expr::Var(String::from(name)))
+}
+
+fn binary(a: Box<Expr>, op: &str, b: Box<Expr>) -> Box<Expr> {
+    Box::new(Expr::Binary(a, String::from(op), b))
+}
+
+fn expr() -> Parser<Expr> {
+    let atom = (identifier() - Expr::Var) | (seq_no_ws("(") >> rec(expr) << seq_no_ws(")"));
+    let op = |a: Expr, op: String, b: Expr| Expr::Binary(Box::new(a), op, Box::new(b));
+    Pratt::new(atom)
+        .ternary(1, seq_no_ws("?"), seq_no_ws(":"), |c, a, b| {
+            Expr::If(Box::new(c), Box::new(a), Box::new(b))
+        })
+        .infix(2, Assoc::None, seq_no_ws("<"), op)
+        .infix(2, Assoc::None, seq_no_ws("=="), op)
+        .infix(3, Assoc::Left, seq_no_ws("+"), op)
+        .prefix(4, seq_no_ws("-"), |_, a| Expr::Neg(Box::new(a)))
+        .prefix(4, seq_no_ws("!"), |_, a| Expr::Not(Box::new(a)))
+        .parser()
+}
+
+#[test]
+fn prefix_test() {
+    assert_eq!(expr().parse("-a"), Ok(Expr::Neg(var("a"))));
+    assert_eq!(expr().parse("!-a"), Ok(Expr::Not(Box::new(Expr::Neg(var("a"))))));
+    assert_eq!(
+        expr().parse("-a + b"),
+        Ok(*binary(Box::new(Expr::Neg(var("a"))), "+", var("b")))
+    );
+}
+
+#[test]
+fn ternary_test() {
+    assert_eq!(
+        expr().parse("a < b ? a : b"),
+        Ok(Expr::If(binary(var("a"), "<", var("b")), var("a"), var("b")))
+    );
+
+    // Ternaries group to the right, and can contain any expression
+    assert_eq!(
+        expr().parse("a ? b ? c : d : e ? f : g"),
+        Ok(Expr::If(
+            var("a"),
+            Box::new(Expr::If(var("b"), var("c"), var("d"))),
+            Box::new(Expr::If(var("e"), var("f"), var("g")))
+        ))
+    );
+}
+
+#[test]
+fn non_assoc_test() {
+    assert_eq!(
+        expr().parse("a + b < c"),
+        Ok(*binary(binary(var("a"), "+", var("b")), "<", var("c")))
+    );
+
+    // Non-associative operators can't be chained
+    let error = (expr() << eof()).parse("a < b < c").unwrap_err();
+    assert_eq!(error.offset(), 6);
+    assert_eq!(error.actual(), "<");
+    assert!(error
+        .expected()
+        .contains(&String::from("anything but <, which is non-associative")));
+}
+
+#[test]
+fn error_test() {
+    // A missing operand is reported where it should be
+    let error = (math() << eof()).parse("1 + (2 * )").unwrap_err();
+    assert_eq!(error.offset(), 9);
+
+    // As are the operators that could have come next
+    let error = (math() << eof()).parse("1 + 2 3").unwrap_err();
+    assert_eq!(error.offset(), 6);
+    assert!(error.expected().contains(&String::from("*")));
+    assert!(error.expected().contains(&String::from("EOF")));
+}
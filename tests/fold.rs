@@ -0,0 +1,44 @@
+extern crate honeycomb;
+use honeycomb::{
+    atoms::{chainl1, chainr1, eof, fold_many, one_of, seq_no_ws, sym},
+    Parser,
+};
+
+fn digit() -> Parser<i64> {
+    one_of(b"0123456789") - |ch| ch.to_digit(10).unwrap() as i64
+}
+
+#[test]
+fn chainl1_test() {
+    let p = chainl1(digit(), sym('-') - |_| |a: i64, b: i64| a - b) << eof();
+    assert_eq!(p.parse("9-3-2"), Ok(4));
+    assert_eq!(p.parse("7"), Ok(7));
+    assert!(p.parse("").is_err());
+    // The error points at the missing term after the operator
+    assert_eq!(p.parse("9-").unwrap_err().offset(), 2);
+}
+
+#[test]
+fn chainr1_test() {
+    let pow: fn(i64, i64) -> i64 = |a, b| a.pow(b as u32);
+    let p = chainr1(digit(), seq_no_ws("^") - move |_| pow) << eof();
+    assert_eq!(p.parse("2^3^2"), Ok(512));
+    assert_eq!(p.parse("2 ^ 3"), Ok(8));
+    assert_eq!(p.parse("5"), Ok(5));
+
+    let p = chainr1(digit(), sym('-') - |_| |a: i64, b: i64| a - b) << eof();
+    assert_eq!(p.parse("9-3-2"), Ok(8));
+}
+
+#[test]
+fn fold_many_test() {
+    let number = fold_many(digit(), 0, |n, d| n * 10 + d) << eof();
+    assert_eq!(number.parse("1234"), Ok(1234));
+    assert_eq!(number.parse(""), Ok(0));
+    assert!(number.parse("12a").is_err());
+
+    // Each parse starts from the initial value again
+    let count = fold_many(sym('a'), 0usize, |n, _| n + 1);
+    assert_eq!(count.parse("aaa"), Ok(3));
+    assert_eq!(count.parse("aa"), Ok(2));
+}
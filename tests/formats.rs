@@ -0,0 +1,278 @@
+extern crate honeycomb;
+use honeycomb::formats::json::{json, json5, JsonValue, MAX_DEPTH};
+
+/// Documents from the JSONTestSuite that every parser must accept
+const ACCEPT: &[(&str, &str)] = &[
+    ("y_array_arraysWithSpaces", "[[]   ]"),
+    ("y_array_empty-string", "[\"\"]"),
+    ("y_array_empty", "[]"),
+    ("y_array_ending_with_newline", "[\"a\"]"),
+    ("y_array_false", "[false]"),
+    ("y_array_heterogeneous", "[null, 1, \"1\", {}]"),
+    ("y_array_null", "[null]"),
+    ("y_array_with_leading_space", " [1]"),
+    ("y_array_with_several_null", "[1,null,null,null,2]"),
+    ("y_array_with_trailing_space", "[2] "),
+    ("y_number_0e+1", "[0e+1]"),
+    ("y_number_0e1", "[0e1]"),
+    ("y_number_after_space", "[ 4]"),
+    (
+        "y_number_double_close_to_zero",
+        concat!(
+            "[-0.0000000000000000000000000000000000000000",
+            "00000000000000000000000000000000000001]"
+        ),
+    ),
+    ("y_number_int_with_exp", "[20e1]"),
+    ("y_number_minus_zero", "[-0]"),
+    ("y_number_negative_int", "[-123]"),
+    ("y_number_real_capital_e", "[1E22]"),
+    ("y_number_real_capital_e_neg_exp", "[1E-2]"),
+    ("y_number_real_capital_e_pos_exp", "[1E+2]"),
+    ("y_number_real_exponent", "[123e45]"),
+    ("y_number_real_fraction_exponent", "[123.456e78]"),
+    ("y_number_simple_real", "[123.456789]"),
+    ("y_object_basic", "{\"asd\":\"sdf\"}"),
+    ("y_object_duplicated_key", "{\"a\":\"b\",\"a\":\"c\"}"),
+    ("y_object_empty", "{}"),
+    ("y_object_empty_key", "{\"\":0}"),
+    ("y_object_escaped_null_in_key", "{\"foo\\u0000bar\": 42}"),
+    (
+        "y_object_long_strings",
+        concat!(
+            "{\"x\":[{\"id\": \"xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx\"}], ",
+            "\"id\": \"xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx\"}"
+        ),
+    ),
+    ("y_object_with_newlines", "{\n\"a\": \"b\"\n}"),
+    ("y_string_1_2_3_bytes_UTF-8_sequences", "[\"\\u0060\\u012a\\u12AB\"]"),
+    ("y_string_accepted_surrogate_pair", "[\"\\uD801\\udc37\"]"),
+    ("y_string_allowed_escapes", "[\"\\\"\\\\\\/\\b\\f\\n\\r\\t\"]"),
+    ("y_string_comments", "[\"a/*b*/c/*d//e\"]"),
+    ("y_string_escaped_control_character", "[\"\\u0012\"]"),
+    ("y_string_in_array_with_leading_space", "[ \"asd\"]"),
+    ("y_string_nonCharacterInUTF-8_U+FFFF", "[\"\u{ffff}\"]"),
+    ("y_string_unicode_escaped_double_quote", "[\"\\u0022\"]"),
+    ("y_string_utf8", "[\"€𝄞\"]"),
+    ("y_structure_lonely_false", "false"),
+    ("y_structure_lonely_int", "42"),
+    ("y_structure_lonely_negative_real", "-0.1"),
+    ("y_structure_lonely_null", "null"),
+    ("y_structure_lonely_string", "\"asd\""),
+    ("y_structure_trailing_newline", "[\"a\"]\n"),
+    ("y_structure_true_in_array", "[true]"),
+    ("y_structure_whitespace_array", " [] "),
+];
+
+/// Documents from the JSONTestSuite that every parser must reject
+const REJECT: &[(&str, &str)] = &[
+    ("n_array_1_true_without_comma", "[1 true]"),
+    ("n_array_colon_instead_of_comma", "[\"\": 1]"),
+    ("n_array_comma_after_close", "[\"\"],"),
+    ("n_array_double_comma", "[1,,2]"),
+    ("n_array_extra_close", "[\"x\"]]"),
+    ("n_array_extra_comma", "[\"\",]"),
+    ("n_array_just_comma", "[,]"),
+    ("n_array_missing_value", "[   , \"\"]"),
+    ("n_array_number_and_comma", "[1,]"),
+    ("n_array_unclosed", "[\"\""),
+    ("n_incomplete_false", "[fals]"),
+    ("n_incomplete_null", "[nul]"),
+    ("n_number_++", "[++1234]"),
+    ("n_number_+1", "[+1]"),
+    ("n_number_-01", "[-01]"),
+    ("n_number_-2.", "[-2.]"),
+    ("n_number_.2e-3", "[.2e-3]"),
+    ("n_number_0.e1", "[0.e1]"),
+    ("n_number_0e", "[0e]"),
+    ("n_number_1.0e+", "[1.0e+]"),
+    ("n_number_2.e3", "[2.e3]"),
+    ("n_number_hex_1_digit", "[0x1]"),
+    ("n_number_Inf", "[Inf]"),
+    ("n_number_NaN", "[NaN]"),
+    ("n_number_neg_with_garbage_at_end", "[-1x]"),
+    ("n_number_with_leading_zero", "[012]"),
+    ("n_object_bad_value", "[\"x\", truth]"),
+    ("n_object_missing_colon", "{\"a\" b}"),
+    ("n_object_missing_key", "{:\"b\"}"),
+    ("n_object_non_string_key", "{1:1}"),
+    ("n_object_single_quote", "{'a':0}"),
+    ("n_object_trailing_comma", "{\"id\":0,}"),
+    ("n_object_unquoted_key", "{a: \"b\"}"),
+    ("n_object_with_single_string", "{ \"foo\" : \"bar\", \"a\" }"),
+    ("n_single_space", " "),
+    ("n_string_escape_x", "[\"\\x00\"]"),
+    ("n_string_incomplete_surrogate_escape_invalid", "[\"\\uD800\\uD800\\x\"]"),
+    ("n_string_invalid_unicode_escape", "[\"\\uqqqq\"]"),
+    ("n_string_single_quote", "['single quote']"),
+    ("n_string_unescaped_newline", "[\"new\nline\"]"),
+    ("n_string_unescaped_tab", "[\"\t\"]"),
+    ("n_structure_comma_instead_of_closing_brace", "{\"x\": true,"),
+    ("n_structure_double_array", "[][]"),
+    ("n_structure_no_data", ""),
+    ("n_structure_object_with_comment", "{\"a\":/*comment*/\"b\"}"),
+    ("n_structure_trailing_#", "{\"a\":\"b\"}#{}"),
+    ("n_structure_unclosed_array", "[1"),
+];
+
+#[test]
+fn conformance_test() {
+    let json = json();
+    for (name, source) in ACCEPT {
+        assert!(json.parse(source).is_ok(), "{} should be accepted", name);
+    }
+    for (name, source) in REJECT {
+        assert!(json.parse(source).is_err(), "{} should be rejected", name);
+    }
+}
+
+#[test]
+fn nesting_test() {
+    let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+    assert!(json().parse(&nested(MAX_DEPTH)).is_ok());
+    assert!(json5().parse(&nested(MAX_DEPTH)).is_ok());
+    let error = json().parse(&nested(MAX_DEPTH + 1)).unwrap_err();
+    assert_eq!(error.offset(), MAX_DEPTH + 1);
+    assert!(error.is_fatal());
+
+    // Deeper documents are rejected instead of overflowing the stack,
+    // like n_structure_100000_opening_arrays and n_structure_open_array_object
+    assert!(json().parse(&"[".repeat(100_000)).is_err());
+    assert!(json().parse(&"[{\"\":".repeat(50_000)).is_err());
+    assert!(json5().parse(&"{a:".repeat(100_000)).is_err());
+}
+
+#[test]
+fn value_test() {
+    assert_eq!(
+        json().parse(r#"{"a": [1, -2.5e2, true, null], "b": "\u00e9\ud83d\ude00"}"#),
+        Ok(JsonValue::Object(vec![
+            (
+                String::from("a"),
+                JsonValue::Array(vec![
+                    JsonValue::Num(1.0),
+                    JsonValue::Num(-250.0),
+                    JsonValue::Bool(true),
+                    JsonValue::Null,
+                ])
+            ),
+            (String::from("b"), JsonValue::Str(String::from("é😀"))),
+        ]))
+    );
+
+    // A lone surrogate can't be turned into a string
+    assert!(json().parse(r#""\ud83d""#).is_err());
+}
+
+#[test]
+fn duplicate_key_test() {
+    let value = json().parse(r#"{"a": 1, "b": 2, "a": 3}"#).unwrap();
+    assert_eq!(value.get("a"), Some(&JsonValue::Num(3.0)));
+    match value {
+        JsonValue::Object(members) => assert_eq!(members.len(), 3),
+        _ => panic!("expected an object"),
+    }
+}
+
+#[test]
+fn serialize_test() {
+    let source = r#"{"a":[1,-2.5,true,null],"b":"quote \" slash \\ tab \t \u0001 é","a":{}}"#;
+    let value = json().parse(source).unwrap();
+    let text = value.to_string();
+    assert_eq!(
+        text,
+        "{\"a\":[1,-2.5,true,null],\"b\":\"quote \\\" slash \\\\ tab \\t \\u0001 é\",\"a\":{}}"
+    );
+    assert_eq!(json().parse(&text), Ok(value));
+    assert_eq!(JsonValue::Num(f64::NAN).to_string(), "null");
+}
+
+/// JSON5 documents, and strict JSON documents with the same value
+const RELAXED: &[(&str, &str)] = &[
+    ("// a comment\n[1, 2]", "[1, 2]"),
+    ("[1, 2, /* a comment */]", "[1, 2]"),
+    ("[\n  1, // one\n  2, // two\n]", "[1, 2]"),
+    ("{a: 1, b_2: [3,],}", r#"{"a": 1, "b_2": [3]}"#),
+    (
+        "{'a': 'single', \"b\": \"double\"}",
+        r#"{"a": "single", "b": "double"}"#,
+    ),
+    (r#"'it\'s "quoted"'"#, r#""it's \"quoted\"""#),
+    ("[0x1F, -0xff, 0XA, +5]", "[31, -255, 10, 5]"),
+    ("{name: 'a', name: 'b'}", r#"{"name": "a", "name": "b"}"#),
+    ("/* before */ null /* after */", "null"),
+    ("{$a: 1, _b: 2, \\u0063d: 3}", r#"{"$a": 1, "_b": 2, "cd": 3}"#),
+    (
+        "{aKeyThatIsLongerThanThirtyOneCharacters: 1, caf\u{e9}: 2}",
+        r#"{"aKeyThatIsLongerThanThirtyOneCharacters": 1, "caf\u00e9": 2}"#,
+    ),
+    ("[.5, 5., -.5e1, 5.e-1]", "[0.5, 5, -5, 0.5]"),
+    (r"'\0\x41\v\a'", r#""\u0000A\u000ba""#),
+    ("'one \\\ntwo \\\r\nthree'", r#""one two three""#),
+    ("'a\tb'", r#""a\tb""#),
+    ("\u{feff}[1,\u{a0}2]\u{3000}", "[1, 2]"),
+    ("{a:\u{2028}1,\u{2029}b:\u{2003}2}", r#"{"a": 1, "b": 2}"#),
+    ("\x0B[\x0C]", "[]"),
+];
+
+#[test]
+fn json5_test() {
+    // Every JSON document is a JSON5 document with the same value
+    for (name, source) in ACCEPT {
+        assert_eq!(json5().parse(source), json().parse(source), "{}", name);
+    }
+
+    for (relaxed, strict) in RELAXED {
+        assert_eq!(json5().parse(relaxed), json().parse(strict), "{}", relaxed);
+        assert!(
+            json().parse(relaxed).is_err(),
+            "{} isn't strict JSON",
+            relaxed
+        );
+    }
+}
+
+#[test]
+fn json5_numbers_test() {
+    assert_eq!(json5().parse("Infinity"), Ok(JsonValue::Num(f64::INFINITY)));
+    assert_eq!(
+        json5().parse("-Infinity"),
+        Ok(JsonValue::Num(f64::NEG_INFINITY))
+    );
+    match json5().parse("NaN") {
+        Ok(JsonValue::Num(n)) => assert!(n.is_nan()),
+        other => panic!("expected NaN, got {:?}", other),
+    }
+    assert!(json().parse("Infinity").is_err());
+    assert!(json().parse("NaN").is_err());
+}
+
+#[test]
+fn json5_reject_test() {
+    for source in &[
+        "[1,,]",
+        "[,]",
+        "{a b}",
+        "{a: 1,,}",
+        "[01]",
+        "/* unclosed",
+        "[0x]",
+        "'a",
+        "[.]",
+        "[5..]",
+        "[.e1]",
+        "{1a: 1}",
+        "{a-b: 1}",
+        "{\\u0031: 1}",
+        r"'\1'",
+        r"'\01'",
+        r"'\x4'",
+        "'a\nb'",
+    ] {
+        assert!(
+            json5().parse(source).is_err(),
+            "{} should be rejected",
+            source
+        );
+    }
+}
@@ -0,0 +1,164 @@
+extern crate honeycomb;
+use honeycomb::{
+    atoms::{eof, none_of, rec, seq, space, sym},
+    indent::{dedent, deeper_indent, indented_block, same_indent, Indent},
+    language::identifier,
+    transform::collect,
+    Parser,
+};
+
+use std::collections::BTreeMap;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Markup {
+    Value(String),
+    Object(BTreeMap<String, Self>),
+    Array(Vec<Self>),
+}
+
+/// Spaces that don't end the line
+fn inline() -> Parser<Vec<char>> {
+    sym(' ').repeat(..)
+}
+
+fn value() -> Parser<Markup> {
+    (none_of(b" \r\n").repeat(1..) - collect) - Markup::Value
+}
+
+fn entry() -> Parser<(String, Markup)> {
+    (identifier() << sym(':')) & rec(body)
+}
+
+fn object() -> Parser<Markup> {
+    indented_block(entry()) - |entries| Markup::Object(entries.into_iter().collect())
+}
+
+fn array() -> Parser<Markup> {
+    indented_block(seq("-") >> rec(body)) - Markup::Array
+}
+
+/// The rest of the line after a key or a dash
+fn body() -> Parser<Markup> {
+    (inline() >> value()) | object() | array()
+}
+
+fn document() -> Parser<Markup> {
+    object() << space() << eof()
+}
+
+fn sample() -> Markup {
+    let size = |width: &str, height: &str| {
+        let mut map = BTreeMap::new();
+        map.insert(String::from("width"), Markup::Value(String::from(width)));
+        map.insert(String::from("height"), Markup::Value(String::from(height)));
+        Markup::Object(map)
+    };
+
+    let mut row = BTreeMap::new();
+    row.insert(String::from("column1"), size("5", "10"));
+    row.insert(String::from("column2"), size("7", "10"));
+    row.insert(String::from("column3"), size("3", "10"));
+    row.insert(
+        String::from("columnlist"),
+        Markup::Array(vec![
+            Markup::Value(String::from("1")),
+            Markup::Value(String::from("2")),
+            Markup::Value(String::from("3")),
+        ]),
+    );
+
+    let mut map = BTreeMap::new();
+    map.insert(String::from("row"), Markup::Object(row));
+    Markup::Object(map)
+}
+
+#[test]
+fn markup_test() {
+    let source = r#"
+row:
+    column1:
+        width: 5
+        height: 10
+    column2:
+        width: 7
+
+        height: 10
+    column3:
+      width: 3
+      height: 10
+    columnlist:
+        - 1
+        - 2
+        - 3
+"#;
+    assert_eq!(document().parse(source), Ok(sample()));
+}
+
+#[test]
+fn nested_array_test() {
+    let mut map = BTreeMap::new();
+    map.insert(
+        String::from("list"),
+        Markup::Array(vec![
+            Markup::Array(vec![
+                Markup::Value(String::from("a")),
+                Markup::Value(String::from("b")),
+            ]),
+            Markup::Value(String::from("c")),
+        ]),
+    );
+    assert_eq!(
+        document().parse("list:\n  -\n    - a\n    - b\n  - c"),
+        Ok(Markup::Object(map))
+    );
+}
+
+#[test]
+fn tabs_test() {
+    let source = "row:\n\tcolumn\n    other\n";
+    let p = |indent: Indent| seq("row:") >> indent.block(identifier()) << space() << eof();
+    assert_eq!(
+        p(Indent::tabs(4)).parse(source),
+        Ok(vec![String::from("column"), String::from("other")])
+    );
+
+    // With the default width of 8, the lines aren't indented the same
+    let error = p(Indent::default()).parse(source).unwrap_err();
+    assert_eq!(error.offset(), 17);
+}
+
+#[test]
+fn offside_error_test() {
+    let error = document().parse("row:\n    a: 1\n  b: 2\n").unwrap_err();
+    assert_eq!(error.offset(), 16);
+
+    let error = document()
+        .parse("row:\n    a: 1\n      b: 2\n")
+        .unwrap_err();
+    assert_eq!(error.offset(), 20);
+    assert!(error
+        .expected()
+        .contains(&String::from("a line indented 4 columns")));
+
+    // A block needs at least one line
+    assert!(document().parse("row:\nnext: 1").is_err());
+}
+
+#[test]
+fn indent_dedent_test() {
+    let p = identifier() & (same_indent() >> identifier());
+    assert_eq!(
+        p.parse("a\n\n  \nb"),
+        Ok((String::from("a"), String::from("b")))
+    );
+    assert_eq!(p.parse("a\n  b").unwrap_err().offset(), 4);
+
+    let p = (seq("a:") >> deeper_indent()) & identifier();
+    assert_eq!(p.parse("a:\n  b"), Ok((2, String::from("b"))));
+    assert!(p.parse("a:\nb").is_err());
+
+    let p = space() >> identifier() << dedent();
+    assert_eq!(p.parse("  a\nb"), Ok(String::from("a")));
+    assert_eq!(p.parse("  a\n\n"), Ok(String::from("a")));
+    assert!(p.parse("  a\n  b").is_err());
+}
@@ -0,0 +1,133 @@
+extern crate honeycomb;
+use honeycomb::{
+    atoms::{byte, bytes, left_rec, list, opt, position, rec, tok, tok_if},
+    expr::{Assoc, Pratt},
+    Error, Parser,
+};
+
+#[test]
+fn bytes_test() {
+    let header = bytes(b"\x89PNG") & (byte(b'\r') >> byte(b'\n'));
+    assert_eq!(
+        header.parse(b"\x89PNG\r\n"),
+        Ok((b"\x89PNG".to_vec(), b'\n'))
+    );
+
+    let error = header.parse(b"GIF89a").unwrap_err();
+    assert_eq!(
+        error,
+        Error::new::<()>("[71, 73, 70, 56]", "[137, 80, 78, 71]", 0).unwrap_err()
+    );
+
+    assert!(header.parse(b"").unwrap_err().at_end());
+
+    // Bytes are one column each, and there are no lines
+    let error = header.parse(b"\x89PNG\n").unwrap_err();
+    assert_eq!(error.actual(), "10");
+    assert_eq!((error.line(), error.column()), (1, 5));
+
+    // Bytes aren't decoded as text
+    assert_eq!(
+        (byte(0xff) & position()).parse(&[0xff, 0x00]),
+        Ok((0xff, 1))
+    );
+}
+
+#[test]
+fn operators_test() {
+    let digit = tok_if(|b: &u8| b.is_ascii_digit());
+    let number = digit * (1..) - |digits| String::from_utf8(digits).unwrap();
+    let numbers = list(number, byte(b','));
+    assert_eq!(
+        numbers.parse(b"12,3,"),
+        Ok(vec![String::from("12"), String::from("3")])
+    );
+
+    let flag = (byte(b'+') | byte(b'-')) & opt(!byte(0) >> byte(b'!'));
+    assert_eq!(flag.parse(b"-!"), Ok((b'-', Some(b'!'))));
+    assert_eq!(flag.parse(b"+\0"), Ok((b'+', None)));
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Num(i64),
+    Plus,
+    Minus,
+    Times,
+    LParen,
+    RParen,
+}
+
+fn num() -> Parser<i64, [Token]> {
+    tok_if(|t| matches!(t, Token::Num(_)))
+        - |t| match t {
+            Token::Num(n) => n,
+            _ => unreachable!(),
+        }
+}
+
+fn math() -> Parser<i64, [Token]> {
+    let atom = num() | (tok(Token::LParen) >> rec(math) << tok(Token::RParen));
+    Pratt::new(atom)
+        .infix(1, Assoc::Left, tok(Token::Plus), |a, _, b| a + b)
+        .infix(1, Assoc::Left, tok(Token::Minus), |a, _, b| a - b)
+        .infix(2, Assoc::Left, tok(Token::Times), |a, _, b| a * b)
+        .prefix(3, tok(Token::Minus), |_, a| -a)
+        .parser()
+}
+
+#[test]
+fn tokens_test() {
+    use Token::*;
+    let tokens = [
+        Num(1),
+        Minus,
+        LParen,
+        Num(2),
+        Plus,
+        Num(3),
+        RParen,
+        Times,
+        Minus,
+        Num(4),
+    ];
+    assert_eq!(math().parse(&tokens), Ok(21));
+
+    let end = !tok_if(|_: &Token| true);
+    let error = (math() << end).parse(&[Num(1), Plus, RParen]).unwrap_err();
+    assert_eq!(error.offset(), 2);
+    assert_eq!(error.actual(), "RParen");
+    assert!(error.expected().contains(&String::from("LParen")));
+}
+
+fn sum() -> Parser<i64, [Token]> {
+    (((left_rec(sum) << tok(Token::Plus)) & num()) - |(a, b)| a + b) | num()
+}
+
+#[test]
+fn left_rec_tokens_test() {
+    use Token::*;
+    assert_eq!(
+        left_rec(sum).parse(&[Num(1), Plus, Num(2), Plus, Num(3)]),
+        Ok(6)
+    );
+}
+
+/// Tokens that only implement what parsing needs
+#[derive(Clone, PartialEq)]
+struct Word(&'static str);
+
+#[test]
+fn opaque_tokens_test() {
+    let word = |w: &'static str| tok_if(move |t: &Word| t.0 == w);
+    let greeting = (word("hello") & word("world")) - |(a, b)| format!("{} {}", a.0, b.0);
+    assert_eq!(
+        greeting.parse(&[Word("hello"), Word("world")]),
+        Ok(String::from("hello world"))
+    );
+
+    // Without Debug, tokens are described by their position
+    let error = greeting.parse(&[Word("hello"), Word("there")]).unwrap_err();
+    assert_eq!(error.actual(), "token 1");
+    assert_eq!(error.column(), 2);
+}
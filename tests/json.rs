@@ -1,6 +1,6 @@
 extern crate honeycomb;
 use honeycomb::{
-    atoms::{rec, seq_no_ws},
+    atoms::{rec, seq_no_ws, Trailing},
     language,
     transform::{to_btree, to_number},
     Parser,
@@ -79,13 +79,27 @@ fn null() -> Parser<JsonValue> {
 }
 
 fn array() -> Parser<JsonValue> {
-    language::array("[", json(), "]") - JsonValue::Array
+    language::array("[", json(), "]", Trailing::Forbid) - JsonValue::Array
 }
 
 fn object() -> Parser<JsonValue> {
-    language::array("{", string() << seq_no_ws(":") & rec(json), "}") - to_btree - JsonValue::Object
+    language::array("{", string() << seq_no_ws(":") & rec(json), "}", Trailing::Forbid) - to_btree - JsonValue::Object
 }
 
 fn json() -> Parser<JsonValue> {
     null() | boolean() | number() | (string() - JsonValue::Str) | rec(array) | rec(object)
 }
+
+#[test]
+fn json_error_test() {
+    let error = json().parse(r#"{"a": 1 "b": 2}"#).unwrap_err();
+    assert_eq!(error.expected(), [",", "}"]);
+    assert_eq!((error.line(), error.column()), (1, 9));
+
+    let error = json().parse("[1, {\n  a: 1\n}]").unwrap_err();
+    assert_eq!(error.offset(), 8);
+    assert_eq!(
+        format!("{}", error),
+        "Expected one of `a string`, `}` but found `a` at 2:3"
+    );
+}
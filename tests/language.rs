@@ -1,7 +1,8 @@
 extern crate honeycomb;
 use honeycomb::{
+    atoms::{seq_no_ws, space, Trailing},
     language::{array, identifier, number, punctuation, string, token},
-    Error,
+    Error, Spanned,
 };
 
 #[test]
@@ -52,7 +53,7 @@ fn punctuation_test() {
 
     assert_eq!(
         punctuation().parse("a"),
-        Error::new("a", "one of ! \" # $ % & ' ( ) * + , - . / : ; < = > ? @ [ \\ ] ^ _ ` { | } ~", "a")
+        Error::new("a", "one of ! \" # $ % & ' ( ) * + , - . / : ; < = > ? @ [ \\ ] ^ _ ` { | } ~", 0)
     );
 }
 
@@ -62,7 +63,7 @@ fn identifier_test() {
     assert_eq!(identifier().parse("testing1"), Ok(String::from("testing1")));
     assert_eq!(
         identifier().parse("123testing"),
-        Error::new("123testing", "an identifier", "123testing")
+        Error::new("1", "an identifier", 0)
     );
     assert_eq!(
         identifier().parse("testing13412341234"),
@@ -97,14 +98,14 @@ fn number_test() {
 
     assert_eq!(
         number_convert.parse("12.33"),
-        Error::new("12.33", "a number", "12.33")
+        Error::new("12.33", "a number", 0)
     );
 }
 
 #[test]
 fn array_test() {
     assert_eq!(
-        array("[", number(), "]").parse("[1, 3, 5.7, 8]"),
+        array("[", number(), "]", Trailing::Forbid).parse("[1, 3, 5.7, 8]"),
         Ok(vec![
             String::from("1"),
             String::from("3"),
@@ -114,7 +115,7 @@ fn array_test() {
     );
 
     assert_eq!(
-        array("[", string(), "]").parse(
+        array("[", string(), "]", Trailing::Allow).parse(
             r#"
 [
     "hey jude",
@@ -154,3 +155,24 @@ fn string_test() {
         Ok(String::from("and make it\n \"better\""))
     );
 }
+
+#[test]
+fn spanned_test() {
+    assert_eq!(
+        identifier().spanned().parse("testing"),
+        Ok(Spanned {
+            value: String::from("testing"),
+            span: 0..7
+        })
+    );
+
+    let assignment =
+        (space() >> identifier().spanned()) & (seq_no_ws("=") >> string().spanned());
+    let source = "  greeting = \"hey jude\"";
+    let (name, value) = assignment.parse(source).unwrap();
+
+    assert_eq!(name.value, "greeting");
+    assert_eq!(&source[name.span], "greeting");
+    assert_eq!(value.value, "hey jude");
+    assert_eq!(&source[value.span], "\"hey jude\"");
+}
@@ -0,0 +1,208 @@
+extern crate honeycomb;
+use honeycomb::{
+    atoms::{eof, left_rec, memo_rec, rec, seq_no_ws, sym},
+    language::{identifier, number},
+    transform::to_number,
+    Parser,
+};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[test]
+fn memo_test() {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    let digit = number().map(|n| {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+        n
+    });
+
+    // Without memoization, the number is parsed once per alternative
+    let unmemoized = (digit.clone() << seq_no_ws("+"))
+        | (digit.clone() << seq_no_ws("-"))
+        | digit.clone();
+    assert_eq!(unmemoized.parse("5"), Ok(String::from("5")));
+    assert_eq!(CALLS.swap(0, Ordering::Relaxed), 3);
+
+    // With it, the number is parsed once and reused
+    let digit = digit.memo();
+    let memoized = (digit.clone() << seq_no_ws("+"))
+        | (digit.clone() << seq_no_ws("-"))
+        | digit.clone();
+    assert_eq!(memoized.parse("5"), Ok(String::from("5")));
+    assert_eq!(CALLS.swap(0, Ordering::Relaxed), 1);
+
+    // Results are only remembered during one call to parse
+    assert_eq!(memoized.parse("6"), Ok(String::from("6")));
+    assert_eq!(CALLS.swap(0, Ordering::Relaxed), 1);
+
+    // Memoized failures report the same error
+    assert_eq!(memoized.parse("x"), unmemoized.parse("x"));
+}
+
+#[test]
+fn memo_rec_test() {
+    static TERMS: AtomicUsize = AtomicUsize::new(0);
+
+    // expr := term '+' expr | term '-' expr | term
+    // term := number | '(' expr ')'
+    fn expr() -> Parser<f64> {
+        (((memo_rec(term) << seq_no_ws("+")) & rec(expr)) - |(a, b)| a + b)
+            | (((memo_rec(term) << seq_no_ws("-")) & rec(expr)) - |(a, b)| a - b)
+            | memo_rec(term)
+    }
+
+    fn term() -> Parser<f64> {
+        TERMS.fetch_add(1, Ordering::Relaxed);
+        (number() - to_number) | (seq_no_ws("(") >> rec(expr) << seq_no_ws(")"))
+    }
+
+    assert_eq!(expr().parse("1 + (2 - 3)"), Ok(0.0));
+
+    // Without memoization, every level of parentheses would parse
+    // the level below it three times, so this would never finish.
+    let depth = 40;
+    let nested = "(".repeat(depth) + "1" + &")".repeat(depth);
+    TERMS.store(0, Ordering::Relaxed);
+    assert_eq!(expr().parse(&nested), Ok(1.0));
+    assert_eq!(TERMS.load(Ordering::Relaxed), depth + 1);
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Math {
+    Number(f64),
+    Add(Box<Math>, Box<Math>),
+    Subtract(Box<Math>, Box<Math>),
+    Multiply(Box<Math>, Box<Math>),
+    Divide(Box<Math>, Box<Math>),
+}
+
+fn num(n: f64) -> Box<Math> {
+    Box::new(Math::Number(n))
+}
+
+// expr   := expr '+' term | expr '-' term | term
+// term   := term '*' factor | term '/' factor | factor
+// factor := number | '(' expr ')'
+fn expr() -> Parser<Math> {
+    (((left_rec(expr) << seq_no_ws("+")) & left_rec(term))
+        - |(a, b)| Math::Add(Box::new(a), Box::new(b)))
+        | (((left_rec(expr) << seq_no_ws("-")) & left_rec(term))
+            - |(a, b)| Math::Subtract(Box::new(a), Box::new(b)))
+        | left_rec(term)
+}
+
+fn term() -> Parser<Math> {
+    (((left_rec(term) << seq_no_ws("*")) & memo_rec(factor))
+        - |(a, b)| Math::Multiply(Box::new(a), Box::new(b)))
+        | (((left_rec(term) << seq_no_ws("/")) & memo_rec(factor))
+            - |(a, b)| Math::Divide(Box::new(a), Box::new(b)))
+        | memo_rec(factor)
+}
+
+fn factor() -> Parser<Math> {
+    (number() - to_number - Math::Number) | (seq_no_ws("(") >> left_rec(expr) << seq_no_ws(")"))
+}
+
+#[test]
+fn left_rec_test() {
+    assert_eq!(left_rec(expr).parse("1"), Ok(Math::Number(1.0)));
+
+    // Subtraction and division associate to the left
+    assert_eq!(
+        left_rec(expr).parse("1 - 2 - 3"),
+        Ok(Math::Subtract(
+            Box::new(Math::Subtract(num(1.0), num(2.0))),
+            num(3.0)
+        ))
+    );
+    assert_eq!(
+        left_rec(expr).parse("8 / 4 / 2"),
+        Ok(Math::Divide(
+            Box::new(Math::Divide(num(8.0), num(4.0))),
+            num(2.0)
+        ))
+    );
+
+    // Multiplication binds tighter than addition
+    assert_eq!(
+        left_rec(expr).parse("1 + 2 * 3 - 4"),
+        Ok(Math::Subtract(
+            Box::new(Math::Add(
+                num(1.0),
+                Box::new(Math::Multiply(num(2.0), num(3.0)))
+            )),
+            num(4.0)
+        ))
+    );
+    assert_eq!(
+        left_rec(expr).parse("(1 - 2) * 3"),
+        Ok(Math::Multiply(
+            Box::new(Math::Subtract(num(1.0), num(2.0))),
+            num(3.0)
+        ))
+    );
+
+    // A failed attempt to grow is still reported
+    let error = (left_rec(expr) << sym(';')).parse("1 + 2 +").unwrap_err();
+    assert_eq!(error.offset(), 7);
+    assert!(error.expected().contains(&String::from("a number")));
+}
+
+// a := b 'x' | 'a'
+// b := a 'y' | 'b'
+fn a() -> Parser<String> {
+    ((left_rec(b) & seq_no_ws("x")) - |(b, x)| b + &x) | seq_no_ws("a")
+}
+
+fn b() -> Parser<String> {
+    ((left_rec(a) & seq_no_ws("y")) - |(a, y)| a + &y) | seq_no_ws("b")
+}
+
+#[test]
+fn indirect_left_rec_test() {
+    assert_eq!(left_rec(a).parse("a"), Ok(String::from("a")));
+    assert_eq!(left_rec(a).parse("bx"), Ok(String::from("bx")));
+    assert_eq!(left_rec(a).parse("ayx"), Ok(String::from("ayx")));
+    assert_eq!(left_rec(a).parse("ayxyx"), Ok(String::from("ayxyx")));
+    assert_eq!(left_rec(b).parse("bxyxy"), Ok(String::from("bxyxy")));
+    assert_eq!(
+        (left_rec(a) << sym(';')).parse("ayxy;").unwrap_err().offset(),
+        4
+    );
+}
+
+// A left recursive list of identifiers: list := list ',' identifier | identifier
+fn names() -> Parser<Vec<String>> {
+    (((left_rec(names) << seq_no_ws(",")) & identifier()) - |(mut v, n)| {
+        v.push(n);
+        v
+    }) | (identifier() - |n| vec![n])
+}
+
+#[test]
+fn left_rec_list_test() {
+    assert_eq!(
+        left_rec(names).parse("a, b, c"),
+        Ok(vec![
+            String::from("a"),
+            String::from("b"),
+            String::from("c")
+        ])
+    );
+}
+
+// joined := joined SEP 'a' | 'a'
+fn joined<const SEP: char>() -> Parser<String> {
+    ((left_rec(joined::<SEP>) & sym(SEP) & sym('a')) - |((s, sep), a)| format!("{}{}{}", s, sep, a))
+        | (sym('a') - |a| a.to_string())
+}
+
+#[test]
+fn generic_rule_test() {
+    // Each instance of a generic rule is a rule of its own,
+    // so they don't share results at the same offset
+    let p = (left_rec(joined::<','>) << eof()) | (left_rec(joined::<'.'>) << eof());
+    assert_eq!(p.parse("a,a"), Ok(String::from("a,a")));
+    assert_eq!(p.parse("a.a.a"), Ok(String::from("a.a.a")));
+}
@@ -0,0 +1,68 @@
+extern crate honeycomb;
+use honeycomb::{
+    atoms::{eof, permutation, permutation_opt, seq_no_ws, space, sym},
+    language::number,
+    Parser,
+};
+
+/// A markup property like `width { 5 }`
+fn property(name: &'static str) -> Parser<String> {
+    (seq_no_ws(name) >> seq_no_ws("{") >> number() << seq_no_ws("}")) % name
+}
+
+fn size() -> Parser<(String, String)> {
+    permutation((property("width"), property("height"))) << space() << eof()
+}
+
+#[test]
+fn permutation_test() {
+    let expected = Ok((String::from("5"), String::from("10")));
+    assert_eq!(size().parse("width { 5 } height { 10 }"), expected);
+    assert_eq!(size().parse("height { 10 } width { 5 }"), expected);
+
+    let p = permutation((sym('a'), sym('b'), sym('c')));
+    for input in &["abc", "acb", "bac", "bca", "cab", "cba"] {
+        assert_eq!(p.parse(input), Ok(('a', 'b', 'c')));
+    }
+}
+
+#[test]
+fn missing_test() {
+    let error = size().parse("height { 10 }").unwrap_err();
+    assert_eq!(error.offset(), 13);
+    assert!(error.expected().contains(&String::from("width")));
+    assert!(!error.expected().contains(&String::from("height")));
+
+    let error = permutation((sym('a'), sym('b'), sym('c')))
+        .parse("b")
+        .unwrap_err();
+    assert_eq!(error.offset(), 1);
+    assert!(error.expected().contains(&String::from("a")));
+    assert!(error.expected().contains(&String::from("c")));
+}
+
+#[test]
+fn duplicate_test() {
+    let error = size()
+        .parse("width { 5 } width { 6 } height { 10 }")
+        .unwrap_err();
+    assert_eq!(error.offset(), 12);
+    assert_eq!(error.expected()[0], "at most one width");
+
+    let p = permutation_opt((sym('a'), sym('b')));
+    assert_eq!(p.parse("aa").unwrap_err().offset(), 1);
+}
+
+#[test]
+fn permutation_opt_test() {
+    let p = permutation_opt((property("width"), property("height"), sym('!'))) << eof();
+    assert_eq!(
+        p.parse("height { 10 }"),
+        Ok((None, Some(String::from("10")), None))
+    );
+    assert_eq!(
+        p.parse("!width { 5 }"),
+        Ok((Some(String::from("5")), None, Some('!')))
+    );
+    assert_eq!(p.parse(""), Ok((None, None, None)));
+}
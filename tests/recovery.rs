@@ -0,0 +1,114 @@
+extern crate honeycomb;
+use honeycomb::{
+    atoms::{delimited_recovery, eof, list, recover_until, seq, seq_no_ws, skip_until},
+    language::{identifier, number},
+    transform::to_number,
+    Parser,
+};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+enum Statement {
+    Let(String, f64),
+    #[default]
+    Error,
+}
+
+fn statement() -> Parser<Statement> {
+    ((seq_no_ws("let") >> identifier() << seq_no_ws("=")) & (number() - to_number))
+        - |(name, value)| Statement::Let(name, value)
+}
+
+fn program() -> Parser<Vec<Statement>> {
+    (recover_until(statement(), seq(";")) << seq_no_ws(";")).repeat(..) << eof()
+}
+
+#[test]
+fn recover_until_test() {
+    let (output, errors) = program().parse_recovering("let a = 1; let = 2; let c = x; let d = 4;");
+    assert_eq!(
+        output,
+        Some(vec![
+            Statement::Let(String::from("a"), 1.0),
+            Statement::Error,
+            Statement::Error,
+            Statement::Let(String::from("d"), 4.0),
+        ])
+    );
+    assert_eq!(
+        errors.iter().map(|e| e.offset()).collect::<Vec<_>>(),
+        [15, 28]
+    );
+    assert_eq!(errors[1].actual(), "x");
+
+    // Without errors, nothing is recovered from
+    let (output, errors) = program().parse_recovering("let a = 1;");
+    assert_eq!(output, Some(vec![Statement::Let(String::from("a"), 1.0)]));
+    assert!(errors.is_empty());
+
+    // `parse` reports the first error
+    assert_eq!(
+        program().parse("let = 1; let b = ?;").unwrap_err().offset(),
+        4
+    );
+}
+
+#[test]
+fn unrecoverable_test() {
+    // The missing semicolon at the end can't be recovered from,
+    // but the errors before it are still reported
+    let (output, errors) = program().parse_recovering("let a = ; let b = 2");
+    assert_eq!(output, None);
+    assert_eq!(errors.len(), 2);
+    assert_eq!((errors[0].line(), errors[0].column()), (1, 9));
+    assert_eq!(errors[1].offset(), 19);
+    assert!(errors[1].expected().contains(&String::from(";")));
+}
+
+#[test]
+fn backtracking_test() {
+    // Recovering inside an alternative that fails anyway isn't an error
+    let p = (recover_until(seq("a") >> seq("b"), seq(";")) >> seq("!")) | seq("ac;");
+    assert_eq!(
+        p.parse_recovering("ac;"),
+        (Some(String::from("ac;")), vec![])
+    );
+}
+
+#[test]
+fn skip_until_test() {
+    let p = skip_until(seq("end")) & seq("end");
+    assert_eq!(
+        p.parse("some ünicode end"),
+        Ok((String::from("some ünicode "), String::from("end")))
+    );
+    assert_eq!(
+        skip_until(seq("end")).parse("no end in sight"),
+        Ok(String::from("no "))
+    );
+    assert_eq!(skip_until(seq("x")).parse("abc"), Ok(String::from("abc")));
+}
+
+fn block() -> Parser<Vec<String>> {
+    seq_no_ws("{") >> list(identifier(), seq_no_ws(",")) << seq_no_ws("}")
+}
+
+#[test]
+fn delimited_recovery_test() {
+    let blocks = delimited_recovery(block(), "{", "}").repeat(..) << eof();
+    let (output, errors) = blocks.parse_recovering("{a, b} {c, 1, {d}} {e}");
+    assert_eq!(
+        output,
+        Some(vec![
+            vec![String::from("a"), String::from("b")],
+            vec![],
+            vec![String::from("e")],
+        ])
+    );
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].offset(), 11);
+
+    // A block that is never closed can't be skipped
+    let (output, errors) = blocks.parse_recovering("{a} {1");
+    assert_eq!(output, None);
+    assert_eq!(errors.len(), 1);
+}
@@ -0,0 +1,85 @@
+extern crate honeycomb;
+use honeycomb::{
+    atoms::{eof, list, opt, space, sym},
+    Parser,
+};
+
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
+use std::ops::RangeBounds;
+
+/// How many `a`s the repetition accepts out of the first few counts
+fn counts(range: impl RangeBounds<usize>) -> Vec<usize> {
+    let p = (sym('a') * range) << eof();
+    (0..8)
+        .filter(|&n| p.parse(&"a".repeat(n)).is_ok())
+        .collect()
+}
+
+#[test]
+fn zero_width_test() {
+    let p = opt(sym('a')) * (..);
+    assert_eq!(p.parse("aa"), Ok(vec![Some('a'), Some('a')]));
+    assert_eq!(p.parse(""), Ok(vec![]));
+
+    let p = (space() * (..)) << sym('x');
+    assert_eq!(p.parse("  x"), Ok(vec![String::from("  ")]));
+    assert_eq!(p.parse("x"), Ok(vec![]));
+}
+
+#[test]
+fn zero_width_list_test() {
+    let p: Parser<Vec<Option<char>>> = list(opt(sym('a')), opt(sym(','))) << eof();
+    assert_eq!(p.parse("a,a,"), Ok(vec![Some('a'), Some('a')]));
+}
+
+#[test]
+#[cfg_attr(
+    debug_assertions,
+    should_panic(expected = "was repeated without consuming input")
+)]
+fn stalled_test() {
+    let error = (opt(sym('a')) * (3..)).parse("a").unwrap_err();
+    assert_eq!(error.offset(), 1);
+}
+
+#[test]
+fn range_forms_test() {
+    assert_eq!(counts(..), vec![0, 1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(counts(2..5), vec![2, 3, 4]);
+    assert_eq!(counts(2..=5), vec![2, 3, 4, 5]);
+    assert_eq!(counts(5..), vec![5, 6, 7]);
+    assert_eq!(counts(..3), vec![0, 1, 2]);
+    assert_eq!(counts(..=3), vec![0, 1, 2, 3]);
+    assert_eq!(counts(3..=3), vec![3]);
+    assert_eq!(counts((Excluded(2), Included(4))), vec![3, 4]);
+    assert_eq!(counts((Excluded(2), Excluded(5))), vec![3, 4]);
+    assert_eq!(counts((Excluded(5), Unbounded::<usize>)), vec![6, 7]);
+    assert_eq!(counts((Bound::<usize>::Unbounded, Excluded(1))), vec![0]);
+}
+
+#[test]
+fn helpers_test() {
+    let exactly = (sym('a').exactly(3) << eof()).parse("aaa");
+    assert_eq!(exactly, Ok(vec!['a'; 3]));
+    assert!((sym('a').exactly(3) << eof()).parse("aaaa").is_err());
+    assert!((sym('a').at_least(2) << eof()).parse("a").is_err());
+    assert_eq!(sym('a').at_least(2).parse("aaaa"), Ok(vec!['a'; 4]));
+    assert_eq!(sym('a').at_most(2).parse("aaaa"), Ok(vec!['a'; 2]));
+    assert_eq!(sym('a').at_most(0).parse("a"), Ok(vec![]));
+}
+
+#[test]
+fn empty_range_test() {
+    let (lower, upper) = (5, 2);
+    for p in [
+        sym('a') * (3..3),
+        sym('a') * (lower..upper),
+        sym('a') * (lower..=upper),
+        sym('a') * (..0),
+        sym('a') * (Excluded(3), Excluded(4)),
+    ] {
+        let error = p.parse("aaaaaa").unwrap_err();
+        assert_eq!(error.offset(), 0);
+        assert!(error.expected()[0].ends_with("which is an empty range"));
+    }
+}
@@ -0,0 +1,76 @@
+extern crate honeycomb;
+use honeycomb::{
+    atoms::{
+        end_by, eof, one_of, sep_by, sep_by1, sep_end_by, sep_end_by1, seq_no_ws, sym, Trailing,
+    },
+    language::{array, identifier, number},
+    Parser,
+};
+
+fn digits(trailing: Trailing) -> Parser<Vec<char>> {
+    sep_by(one_of(b"0123456789"), sym(','), trailing) << eof()
+}
+
+#[test]
+fn sep_by_test() {
+    let p = digits(Trailing::Forbid);
+    assert_eq!(p.parse("1,2,3"), Ok(vec!['1', '2', '3']));
+    assert_eq!(p.parse(""), Ok(vec![]));
+    assert_eq!(p.parse("1,2,").unwrap_err().offset(), 4);
+    assert!(p.parse(",").is_err());
+
+    let p = digits(Trailing::Allow);
+    assert_eq!(p.parse("1,2"), Ok(vec!['1', '2']));
+    assert_eq!(p.parse("1,2,"), Ok(vec!['1', '2']));
+    assert!(p.parse("1,2,,").is_err());
+
+    let p = digits(Trailing::Require);
+    assert_eq!(p.parse("1,2,"), Ok(vec!['1', '2']));
+    assert_eq!(p.parse(""), Ok(vec![]));
+    assert_eq!(p.parse("1,2").unwrap_err().offset(), 3);
+}
+
+#[test]
+fn sep_by1_test() {
+    let p = sep_by1(one_of(b"0123456789"), sym(','), Trailing::Forbid) << eof();
+    assert_eq!(p.parse("1"), Ok(vec!['1']));
+    assert!(p.parse("").is_err());
+
+    let p = sep_end_by1(one_of(b"0123456789"), sym(',')) << eof();
+    assert_eq!(p.parse("1,"), Ok(vec!['1']));
+    assert!(p.parse("").is_err());
+}
+
+#[test]
+fn sep_end_by_test() {
+    let p = sep_end_by(one_of(b"0123456789"), sym(',')) << eof();
+    assert_eq!(p.parse("1,2"), Ok(vec!['1', '2']));
+    assert_eq!(p.parse("1,2,"), Ok(vec!['1', '2']));
+    assert_eq!(p.parse(""), Ok(vec![]));
+
+    let statements = end_by(identifier(), seq_no_ws(";")) << eof();
+    assert_eq!(
+        statements.parse("a; b;"),
+        Ok(vec![String::from("a"), String::from("b")])
+    );
+    assert!(statements.parse("a; b").is_err());
+}
+
+#[test]
+fn array_policy_test() {
+    let args = |trailing| array("(", number(), ")", trailing);
+    assert_eq!(
+        args(Trailing::Forbid).parse("(1, 2)"),
+        Ok(vec![String::from("1"), String::from("2")])
+    );
+    assert_eq!(
+        args(Trailing::Forbid)
+            .parse("(1, 2,)")
+            .unwrap_err()
+            .offset(),
+        6
+    );
+    assert!(args(Trailing::Allow).parse("(1, 2,)").is_ok());
+    assert!(args(Trailing::Require).parse("(1, 2)").is_err());
+    assert_eq!(args(Trailing::Require).parse("()"), Ok(vec![]));
+}
@@ -0,0 +1,115 @@
+extern crate honeycomb;
+use honeycomb::{
+    atoms::{eof, get_state, is, rec, seq_no_ws, set_state, space},
+    language::identifier,
+    Parser,
+};
+
+use std::collections::BTreeSet;
+
+#[test]
+fn with_state_test() {
+    let count = identifier().with_state(|n: &mut usize, name| {
+        *n += 1;
+        name
+    });
+    let names = (count << space()).repeat(..) << eof();
+
+    let mut n = 0usize;
+    assert_eq!(names.parse_with_state("a b c", &mut n).unwrap().len(), 3);
+    assert_eq!(n, 3);
+
+    // Failing leaves the state alone
+    assert!(names.parse_with_state("d e !", &mut n).is_err());
+    assert_eq!(n, 3);
+}
+
+#[test]
+fn rollback_test() {
+    let push = identifier().with_state(|names: &mut Vec<String>, name| names.push(name));
+    let p = (push.clone() >> seq_no_ws("!")) | (push >> seq_no_ws("?"));
+
+    let mut names: Vec<String> = vec![];
+    p.parse_with_state("a?", &mut names).unwrap();
+    assert_eq!(names, vec![String::from("a")]);
+
+    // Lookahead never changes the state
+    let push = identifier().with_state(|names: &mut Vec<String>, name| names.push(name));
+    let mut names: Vec<String> = vec![];
+    is(push).parse_with_state("b", &mut names).unwrap();
+    assert!(names.is_empty());
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Statement {
+    Type(String),
+    Declare(String, String),
+    Multiply(String, String),
+}
+
+fn typename() -> Parser<String> {
+    identifier().with_state(|types: &mut BTreeSet<String>, name| (types.contains(&name), name))
+        ^ |(declared, name)| if declared { Ok(name) } else { Err(()) }
+}
+
+fn statement() -> Parser<Statement> {
+    let typedef = seq_no_ws("type")
+        >> identifier().with_state(|types: &mut BTreeSet<String>, name| {
+            types.insert(name.clone());
+            Statement::Type(name)
+        });
+    let declare = (typename() << seq_no_ws("*")) & identifier();
+    let multiply = (identifier() << seq_no_ws("*")) & identifier();
+    (typedef
+        | (declare - |(t, name)| Statement::Declare(t, name))
+        | (multiply - |(a, b)| Statement::Multiply(a, b)))
+        << seq_no_ws(";")
+}
+
+#[test]
+fn declared_types_test() {
+    let program = statement().repeat(..) << eof();
+    let mut types = BTreeSet::<String>::new();
+    assert_eq!(
+        program.parse_with_state("a * b; type a; a * b;", &mut types),
+        Ok(vec![
+            Statement::Multiply(String::from("a"), String::from("b")),
+            Statement::Type(String::from("a")),
+            Statement::Declare(String::from("a"), String::from("b")),
+        ])
+    );
+    assert!(types.contains("a"));
+}
+
+fn nested() -> Parser<usize> {
+    let deeper = get_state::<usize, _>() ^ |depth| if depth < 3 { Ok(depth + 1) } else { Err(()) };
+    let open = (seq_no_ws("(") >> deeper).with_state(|depth: &mut usize, d| *depth = d);
+    let close = seq_no_ws(")").with_state(|depth: &mut usize, _| *depth -= 1);
+    ((open >> rec(nested) << close) - |n| n + 1) | (seq_no_ws("x") - |_| 0)
+}
+
+#[test]
+fn depth_limit_test() {
+    let mut depth = 0usize;
+    assert_eq!(nested().parse_with_state("((x))", &mut depth), Ok(2));
+    assert_eq!(depth, 0);
+    assert_eq!(nested().parse_with_state("(((x)))", &mut depth), Ok(3));
+    assert!(nested().parse_with_state("((((x))))", &mut depth).is_err());
+}
+
+#[test]
+fn set_state_test() {
+    let p = set_state(5usize) >> get_state::<usize, _>();
+    assert_eq!(p.parse_with_state("", &mut 0usize), Ok(5));
+
+    let p = (set_state(1usize) >> seq_no_ws("a")) | (get_state::<usize, _>() - |n| n.to_string());
+    let mut n = 0usize;
+    assert_eq!(p.parse_with_state("b", &mut n), Ok(String::from("0")));
+    assert_eq!(n, 0);
+}
+
+#[test]
+#[should_panic]
+fn no_state_test() {
+    let _ = get_state::<usize, str>().parse("");
+}
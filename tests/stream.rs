@@ -0,0 +1,93 @@
+extern crate honeycomb;
+use honeycomb::{
+    atoms::{none_of, seq, sym},
+    language::{alpha, number},
+    transform::{collect, to_number},
+    Error, Parser, Partial,
+};
+
+use std::cell::Cell;
+
+#[test]
+fn partial_test() {
+    assert_eq!(
+        seq("abc").parse_partial("abcd"),
+        Partial::Done(String::from("abc"), 3)
+    );
+    assert_eq!(seq("abc").parse_partial("ab"), Partial::Incomplete);
+    assert_eq!(
+        seq("abc").parse_partial("abd"),
+        Partial::Failed(Error::new::<()>("abd", "abc", 0).unwrap_err())
+    );
+
+    // More digits could be on the way
+    let n = number() - to_number::<f64>;
+    assert_eq!(n.parse_partial("12"), Partial::Incomplete);
+    assert_eq!(n.parse_partial("12;"), Partial::Done(12.0, 2));
+}
+
+fn line() -> Parser<String> {
+    ((none_of(b"\n") * ..) - collect) << sym('\n')
+}
+
+#[test]
+fn stream_test() {
+    let chunks = ["fir", "st\nsec", "ond\n", "", "third\n"];
+    let lines = line().stream(chunks).collect::<Result<Vec<_>, _>>();
+    assert_eq!(
+        lines,
+        Ok(vec![
+            String::from("first"),
+            String::from("second"),
+            String::from("third")
+        ])
+    );
+
+    assert_eq!(line().stream(Vec::<String>::new()).count(), 0);
+}
+
+#[test]
+fn lazy_test() {
+    // Items are yielded as soon as they are complete,
+    // without reading the rest of the stream
+    let read = Cell::new(0);
+    let chunks = ["1;2", ";3;", "4;"]
+        .iter()
+        .inspect(|_| read.set(read.get() + 1));
+    let mut numbers = ((number() - to_number::<f64>) << sym(';')).stream(chunks);
+    assert_eq!(numbers.next(), Some(Ok(1.0)));
+    assert_eq!(read.get(), 1);
+    assert_eq!(numbers.next(), Some(Ok(2.0)));
+    assert_eq!(read.get(), 2);
+    assert_eq!(numbers.collect::<Vec<_>>(), vec![Ok(3.0), Ok(4.0)]);
+}
+
+#[test]
+fn stream_error_test() {
+    let words = ((alpha() * (1..)) - collect) << sym('\n');
+    let mut stream = words.stream(["ab\nc", "d\nef1\n", "gh\n"]);
+    assert_eq!(stream.next(), Some(Ok(String::from("ab"))));
+    assert_eq!(stream.next(), Some(Ok(String::from("cd"))));
+
+    // Errors are located in the whole stream
+    let error = stream.next().unwrap().unwrap_err();
+    assert_eq!(error.offset(), 8);
+    assert_eq!((error.line(), error.column()), (3, 3));
+
+    // The stream stops after an error
+    assert_eq!(stream.next(), None);
+
+    // The last item can't be cut off
+    let mut stream = line().stream(["a\nb"]);
+    assert_eq!(stream.next(), Some(Ok(String::from("a"))));
+    let error = stream.next().unwrap().unwrap_err();
+    assert_eq!((error.offset(), error.line(), error.column()), (3, 2, 2));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn reader_test() {
+    let reader = std::io::Cursor::new("one\ntwo\n");
+    let lines = line().stream_reader(reader).collect::<Result<Vec<_>, _>>();
+    assert_eq!(lines, Ok(vec![String::from("one"), String::from("two")]));
+}
@@ -0,0 +1,53 @@
+extern crate honeycomb;
+use honeycomb::{
+    atoms::{eof, one_of, seq_no_ws, sym, take},
+    language::{identifier, number},
+    Parser,
+};
+
+fn length() -> Parser<usize> {
+    (one_of(b"0123456789").repeat(1..) ^ |digits| digits.iter().collect::<String>().parse())
+        << sym(':')
+}
+
+#[test]
+fn length_prefix_test() {
+    let field = length().then(take) << eof();
+    assert_eq!(field.parse("5:hello"), Ok(String::from("hello")));
+    assert_eq!(field.parse("0:"), Ok(String::new()));
+    assert_eq!(field.parse("3:hello").unwrap_err().offset(), 5);
+    assert_eq!(field.parse("9:hello").unwrap_err().offset(), 2);
+
+    // Lengths count bytes, and must end between characters
+    assert_eq!(field.parse("2:é"), Ok(String::from("é")));
+    assert!(field.parse("1:é").is_err());
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Field {
+    Number(String),
+    Name(String),
+}
+
+#[test]
+fn header_test() {
+    let field = (identifier() << seq_no_ws(":")).then(|kind| match kind.as_str() {
+        "number" => number() - Field::Number,
+        _ => identifier() - Field::Name,
+    });
+    assert_eq!(
+        field.parse("number: 12"),
+        Ok(Field::Number(String::from("12")))
+    );
+    assert_eq!(field.parse("name: ab"), Ok(Field::Name(String::from("ab"))));
+    assert!(field.parse("number: ab").is_err());
+}
+
+#[test]
+fn take_test() {
+    assert_eq!(take(3).parse("abcdef"), Ok(String::from("abc")));
+    assert_eq!(take(0).parse(""), Ok(String::new()));
+    let error = take(3).parse("ab").unwrap_err();
+    assert_eq!(error.offset(), 0);
+    assert_eq!(error.expected(), ["3 bytes"]);
+}
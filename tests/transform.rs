@@ -5,7 +5,9 @@ use honeycomb::{
     transform::{collect, to_btree, to_number, to_string, unwrap_opt},
 };
 
+use std::cell::RefCell;
 use std::collections::BTreeMap;
+use std::rc::Rc;
 
 #[test]
 fn collect_test() {
@@ -72,3 +74,38 @@ fn unwrap_opt_test() {
         Ok(String::from("1298712.9"))
     );
 }
+
+#[test]
+fn closure_test() {
+    // Captured values can be used in a mapping step
+    let scale = 10.0;
+    let scaled = number() - to_number - move |n: f64| n * scale;
+    assert_eq!(scaled.parse("1.5"), Ok(15.0));
+
+    // Including shared state, like an interner
+    let interned = Rc::new(RefCell::new(Vec::<String>::new()));
+    let table = interned.clone();
+    let symbol = string().map(move |s| {
+        let mut table = table.borrow_mut();
+        match table.iter().position(|t| *t == s) {
+            Some(n) => n,
+            None => {
+                table.push(s);
+                table.len() - 1
+            }
+        }
+    });
+    let symbols = (space() >> symbol << space()) * (..);
+    assert_eq!(
+        symbols.parse(r#""a" "b" "a" "c" "b""#),
+        Ok(vec![0, 1, 0, 2, 1])
+    );
+    assert_eq!(*interned.borrow(), vec!["a", "b", "c"]);
+
+    // And in conversions
+    let limit = 100;
+    let bounded = (number() - to_number::<i32>)
+        ^ move |n: i32| if n <= limit { Ok(n) } else { Err(n) };
+    assert_eq!(bounded.parse("42"), Ok(42));
+    assert!(bounded.parse("420").is_err());
+}